@@ -1,173 +1,323 @@
-use std::str::FromStr;
+use std::{collections::BTreeSet, fmt::Display};
 
-#[derive(Debug, PartialEq)]
-enum Move {
-    Rock,
-    Paper,
-    Scissors,
+use itertools::Itertools;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Outcome {
+    Lose,
+    Draw,
+    Win,
 }
 
-#[derive(Debug, PartialEq)]
-struct InitialMove {
-    value: Move,
+impl Outcome {
+    const ALL: [Outcome; 3] = [Outcome::Lose, Outcome::Draw, Outcome::Win];
 }
 
-#[derive(Debug, PartialEq)]
-struct ResponseMove {
-    value: Move,
+impl Display for Outcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Outcome::Lose => write!(f, "lose"),
+            Outcome::Draw => write!(f, "draw"),
+            Outcome::Win => write!(f, "win"),
+        }
+    }
 }
 
-#[derive(Debug, PartialEq)]
-enum Outcome {
-    Win,
-    Lose,
-    Draw,
+// A game where every move beats half of the others and loses to the rest, like
+// rock-paper-scissors. The moves go around a circle in which each one beats the
+// (n - 1) / 2 moves before it: paper beats rock, scissors beats paper, and rock
+// wraps around to beat scissors. Moves are referred to by their index.
+#[derive(Debug, Clone)]
+struct Game {
+    moves: Vec<String>,
+    // Points for choosing each move
+    move_scores: Vec<u32>,
+    // Points for losing, drawing and winning, in that order
+    outcome_scores: [u32; 3],
 }
 
-impl ResponseMove {
-    fn selection_score(&self) -> usize {
-        match self.value {
-            Move::Rock => 1,
-            Move::Paper => 2,
-            Move::Scissors => 3,
+impl Game {
+    // Scores the way the puzzle does: 1 point for the first move, 2 for the
+    // second and so on, plus 0 for a loss, 3 for a draw and 6 for a win
+    fn new(moves: &[&str]) -> Result<Self, String> {
+        if moves.len() < 3 || moves.len().is_multiple_of(2) {
+            return Err(format!(
+                "Moves can't beat as many moves as they lose to with {} of them",
+                moves.len()
+            ));
         }
+        Ok(Self {
+            moves: moves.iter().map(|name| name.to_string()).collect(),
+            move_scores: (1..=moves.len() as u32).collect(),
+            outcome_scores: [0, 3, 6],
+        })
     }
-    fn game_score(&self, initial: &InitialMove) -> usize {
-        let outcome = match (&self.value, &initial.value) {
-            // Draws
-            (Move::Rock, Move::Rock) => Outcome::Draw,
-            (Move::Paper, Move::Paper) => Outcome::Draw,
-            (Move::Scissors, Move::Scissors) => Outcome::Draw,
-            // Wins
-            (Move::Rock, Move::Scissors) => Outcome::Win,
-            (Move::Scissors, Move::Paper) => Outcome::Win,
-            (Move::Paper, Move::Rock) => Outcome::Win,
-            // Losses
-            (Move::Rock, Move::Paper) => Outcome::Lose,
-            (Move::Scissors, Move::Rock) => Outcome::Lose,
-            (Move::Paper, Move::Scissors) => Outcome::Lose,
-        };
-        match outcome {
-            Outcome::Win => 6,
-            Outcome::Lose => 0,
-            Outcome::Draw => 3,
+
+    fn rock_paper_scissors() -> Self {
+        Self::new(&["Rock", "Paper", "Scissors"]).unwrap()
+    }
+
+    // In the order where every move beats the two before it
+    fn rock_paper_scissors_lizard_spock() -> Self {
+        Self::new(&["Rock", "Spock", "Paper", "Lizard", "Scissors"]).unwrap()
+    }
+
+    fn with_scores(
+        mut self,
+        move_scores: &[u32],
+        outcome_scores: [u32; 3],
+    ) -> Result<Self, String> {
+        if move_scores.len() != self.moves.len() {
+            return Err(format!(
+                "Expected a score for each of the {} moves, found {}",
+                self.moves.len(),
+                move_scores.len()
+            ));
         }
+        self.move_scores = move_scores.to_vec();
+        self.outcome_scores = outcome_scores;
+        Ok(self)
     }
-    fn total_score(&self, initial: &InitialMove) -> usize {
-        self.selection_score() + self.game_score(initial)
+
+    fn outcome(&self, mine: usize, theirs: usize) -> Outcome {
+        let n = self.moves.len();
+        match (mine + n - theirs) % n {
+            0 => Outcome::Draw,
+            distance if distance <= n / 2 => Outcome::Win,
+            _ => Outcome::Lose,
+        }
     }
-}
 
-impl InitialMove {
-    fn force_outcome(&self, outcome: Outcome) -> ResponseMove {
-        let value = match (&self.value, outcome) {
-            (Move::Rock, Outcome::Win) => Move::Paper,
-            (Move::Rock, Outcome::Lose) => Move::Scissors,
-            (Move::Rock, Outcome::Draw) => Move::Rock,
-            (Move::Paper, Outcome::Win) => Move::Scissors,
-            (Move::Paper, Outcome::Lose) => Move::Rock,
-            (Move::Paper, Outcome::Draw) => Move::Paper,
-            (Move::Scissors, Outcome::Win) => Move::Rock,
-            (Move::Scissors, Outcome::Lose) => Move::Paper,
-            (Move::Scissors, Outcome::Draw) => Move::Scissors,
-        };
-        ResponseMove { value }
+    fn score(&self, mine: usize, theirs: usize) -> u32 {
+        self.move_scores[mine] + self.outcome_scores[self.outcome(mine, theirs) as usize]
+    }
+
+    // The best scoring move that ends the round the way we want
+    fn response(&self, theirs: usize, outcome: Outcome) -> usize {
+        (0..self.moves.len())
+            .filter(|&mine| self.outcome(mine, theirs) == outcome)
+            .max_by_key(|&mine| self.move_scores[mine])
+            .unwrap()
     }
 }
 
-impl FromStr for InitialMove {
-    type Err = String;
+// A line of the strategy guide: the move the opponent will make, and a letter
+// that we have to work out the meaning of
+type Round = (usize, char);
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let value = match s {
-            "A" => Move::Rock,
-            "B" => Move::Paper,
-            "C" => Move::Scissors,
-            _ => panic!("Invalid move"),
-        };
+// Input is the opponent's move as a letter from A on, and then our letter.
+// Example:
+// A Y
+// B X
+// C Z
+fn parse(input: &str, game: &Game) -> Result<Vec<Round>, String> {
+    let letter = |s: &str| match s.chars().collect::<Vec<_>>()[..] {
+        [c] => Ok(c),
+        _ => Err(format!("Expected a single letter, found {:?}", s)),
+    };
+    input
+        .lines()
+        .map(|line| {
+            let (theirs, ours) = line
+                .split_once(' ')
+                .ok_or(format!("Expected two columns in {:?}", line))?;
+            let theirs = (letter(theirs)? as usize)
+                .checked_sub('A' as usize)
+                .filter(|&theirs| theirs < game.moves.len())
+                .ok_or(format!("Unknown move {:?}", theirs))?;
+            Ok((theirs, letter(ours)?))
+        })
+        .collect()
+}
 
-        Ok(InitialMove { value: value })
-    }
+// The letters the puzzle uses in the second column
+const LETTERS: [char; 3] = ['X', 'Y', 'Z'];
+
+// What the letters in the second column stand for
+#[derive(Debug, Clone, PartialEq)]
+enum Reading {
+    Moves(Vec<(char, usize)>),
+    Outcomes(Vec<(char, Outcome)>),
 }
 
-impl FromStr for ResponseMove {
-    type Err = String;
+fn lookup<T: Copy>(meanings: &[(char, T)], letter: char) -> Option<T> {
+    meanings
+        .iter()
+        .find(|(c, _)| *c == letter)
+        .map(|(_, meaning)| *meaning)
+}
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let value = match s {
-            "X" => Move::Rock,
-            "Y" => Move::Paper,
-            "Z" => Move::Scissors,
-            _ => panic!("Invalid move"),
-        };
+impl Reading {
+    // Part one's reading: X, Y and Z are the moves in order
+    fn moves_in_order() -> Self {
+        Reading::Moves(LETTERS.into_iter().zip(0..).collect())
+    }
+
+    // Part two's reading: X, Y and Z mean we have to lose, draw or win
+    fn outcomes_in_order() -> Self {
+        Reading::Outcomes(LETTERS.into_iter().zip(Outcome::ALL).collect())
+    }
+
+    // None if the guide uses a letter this reading has no meaning for
+    fn score(&self, game: &Game, rounds: &[Round]) -> Option<u32> {
+        rounds
+            .iter()
+            .map(|&(theirs, letter)| match self {
+                Reading::Moves(meanings) => {
+                    let mine = lookup(meanings, letter).filter(|&mine| mine < game.moves.len())?;
+                    Some(game.score(mine, theirs))
+                }
+                Reading::Outcomes(meanings) => {
+                    let outcome = lookup(meanings, letter)?;
+                    Some(game.score(game.response(theirs, outcome), theirs))
+                }
+            })
+            .sum()
+    }
 
-        Ok(ResponseMove { value: value })
+    fn describe(&self, game: &Game) -> String {
+        match self {
+            Reading::Moves(meanings) => meanings
+                .iter()
+                .map(|(letter, mine)| format!("{} = {}", letter, game.moves[*mine]))
+                .join(", "),
+            Reading::Outcomes(meanings) => meanings
+                .iter()
+                .map(|(letter, outcome)| format!("{} = {}", letter, outcome))
+                .join(", "),
+        }
     }
 }
 
-impl FromStr for Outcome {
-    type Err = String;
+// The second column read the way each part of the puzzle does, and whichever
+// assignment of meanings to the letters in the guide would score the most
+#[derive(Debug)]
+struct Analysis {
+    as_moves: Option<(Reading, u32)>,
+    as_outcomes: Option<(Reading, u32)>,
+    best: Option<(Reading, u32)>,
+}
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let value = match s {
-            "X" => Outcome::Lose,
-            "Y" => Outcome::Draw,
-            "Z" => Outcome::Win,
-            _ => panic!("Invalid outcome"),
+impl Analysis {
+    fn new(game: &Game, rounds: &[Round]) -> Self {
+        let letters: Vec<char> = rounds
+            .iter()
+            .map(|(_, letter)| *letter)
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+        let scored = |reading: Reading| {
+            let score = reading.score(game, rounds)?;
+            Some((reading, score))
         };
-
-        Ok(value)
+        let as_moves = (0..game.moves.len())
+            .permutations(letters.len())
+            .map(|moves| Reading::Moves(letters.iter().copied().zip(moves).collect()));
+        let as_outcomes = Outcome::ALL
+            .into_iter()
+            .permutations(letters.len())
+            .map(|outcomes| Reading::Outcomes(letters.iter().copied().zip(outcomes).collect()));
+        Analysis {
+            as_moves: scored(Reading::moves_in_order()),
+            as_outcomes: scored(Reading::outcomes_in_order()),
+            best: as_moves
+                .chain(as_outcomes)
+                .filter_map(scored)
+                .max_by_key(|(_, score)| *score),
+        }
     }
-}
 
-fn parse_input(input: &str) -> Vec<(InitialMove, ResponseMove)> {
-    // This was entirely generated by Copilot on the first try after I had implemented the above types
-    let mut moves = Vec::new();
-    for line in input.lines() {
-        let mut parts = line.split_whitespace();
-        let initial_move = parts.next().unwrap().parse::<InitialMove>().unwrap();
-        let response_move = parts.next().unwrap().parse::<ResponseMove>().unwrap();
-        moves.push((initial_move, response_move));
+    fn report(&self, game: &Game) -> String {
+        let line = |title: &str, reading: &Option<(Reading, u32)>| match reading {
+            Some((reading, score)) => {
+                format!("{}: {} ({})", title, score, reading.describe(game))
+            }
+            None => format!("{}: not possible", title),
+        };
+        [
+            line("As moves", &self.as_moves),
+            line("As outcomes", &self.as_outcomes),
+            line("Best", &self.best),
+        ]
+        .join("\n")
     }
-    moves
 }
 
-fn parse_input_part_two(input: &str) -> Vec<(InitialMove, Outcome)> {
-    // This was entirely generated by Copilot on the first try after I had implemented the above types
-    let mut games = Vec::new();
-    for line in input.lines() {
-        let mut parts = line.split_whitespace();
-        let initial_move = parts.next().unwrap().parse::<InitialMove>().unwrap();
-        let outcome = parts.next().unwrap().parse::<Outcome>().unwrap();
-        games.push((initial_move, outcome));
-    }
-    games
+// The game to analyze the guide with. `game` is "rps" or "rpsls", and the
+// scores are comma separated: one for each move, and then one each for losing,
+// drawing and winning. Anything left out is scored the way the puzzle does.
+fn configure(
+    game: Option<&str>,
+    move_scores: Option<&str>,
+    outcome_scores: Option<&str>,
+) -> Result<Game, String> {
+    let game = match game.unwrap_or("rps") {
+        "rps" => Game::rock_paper_scissors(),
+        "rpsls" => Game::rock_paper_scissors_lizard_spock(),
+        other => return Err(format!("Unknown game {}, expected rps or rpsls", other)),
+    };
+    let numbers = |s: &str| {
+        s.split(',')
+            .map(|n| {
+                n.trim()
+                    .parse::<u32>()
+                    .map_err(|e| format!("Bad score {:?}: {}", n, e))
+            })
+            .collect::<Result<Vec<_>, _>>()
+    };
+    let move_scores = match move_scores {
+        Some(s) => numbers(s)?,
+        None => game.move_scores.clone(),
+    };
+    let outcome_scores = match outcome_scores {
+        Some(s) => numbers(s)?
+            .try_into()
+            .map_err(|_| "Expected three outcome scores: lose, draw and win".to_string())?,
+        None => game.outcome_scores,
+    };
+    game.with_scores(&move_scores, outcome_scores)
 }
 
 pub fn part_one(input: &str) -> Option<u32> {
     // First submission: "That's not the right answer; your answer is too high." `13796`
     // Average score should be (2 + 3) * 2500 = 12500, so it's in the right ballpark
-    // Realized that I flipped around which thing gets scored: ResponseMove should be scored, not InitialMove
-    let moves = parse_input(input);
-    let mut score = 0;
-    for (initial_move, response_move) in moves {
-        score += response_move.total_score(&initial_move) as u32;
-    }
-    Some(score)
+    // Realized that I flipped around which thing gets scored: our move should be scored, not theirs
+    let game = Game::rock_paper_scissors();
+    let rounds = parse(input, &game).ok()?;
+    Reading::moves_in_order().score(&game, &rounds)
 }
 
 pub fn part_two(input: &str) -> Option<u32> {
-    let games = parse_input_part_two(input);
-    let mut score = 0;
-    for (initial_move, outcome) in games {
-        let response_move = initial_move.force_outcome(outcome);
-        score += response_move.total_score(&initial_move) as u32;
-    }
-    Some(score)
+    let game = Game::rock_paper_scissors();
+    let rounds = parse(input, &game).ok()?;
+    Reading::outcomes_in_order().score(&game, &rounds)
 }
 
 fn main() {
     let input = &advent_of_code::read_file("inputs", 2);
+    // `cargo solve 02 -- --analyze` shows what the strategy guide scores under
+    // every reading of the second column. Add `--game rpsls` to play
+    // rock-paper-scissors-lizard-Spock, and `--move-scores 5,4,3,2,1` or
+    // `--outcome-scores 0,1,2` to score it differently.
+    let mut args = pico_args::Arguments::from_env();
+    if args.contains("--analyze") {
+        let game: Option<String> = args.opt_value_from_str("--game").unwrap();
+        let move_scores: Option<String> = args.opt_value_from_str("--move-scores").unwrap();
+        let outcome_scores: Option<String> = args.opt_value_from_str("--outcome-scores").unwrap();
+        let report = configure(
+            game.as_deref(),
+            move_scores.as_deref(),
+            outcome_scores.as_deref(),
+        )
+        .and_then(|game| {
+            let rounds = parse(input, &game)?;
+            Ok(Analysis::new(&game, &rounds).report(&game))
+        });
+        match report {
+            Ok(report) => println!("{}\n", report),
+            Err(error) => eprintln!("{}\n", error),
+        }
+    }
     advent_of_code::solve!(1, part_one, input);
     advent_of_code::solve!(2, part_two, input);
 }
@@ -185,6 +335,66 @@ mod tests {
     #[test]
     fn test_part_two() {
         let input = advent_of_code::read_file("examples", 2);
-        assert_eq!(part_two(&input), None);
+        assert_eq!(part_two(&input), Some(12));
+    }
+
+    #[test]
+    fn test_analysis() {
+        let input = advent_of_code::read_file("examples", 2);
+        let game = Game::rock_paper_scissors();
+        let rounds = parse(&input, &game).unwrap();
+        let analysis = Analysis::new(&game, &rounds);
+        // Winning every round takes a different move each time, which is
+        // worth 2 + 3 + 1 + 3 * 6
+        assert_eq!(
+            analysis.report(&game),
+            "As moves: 15 (X = Rock, Y = Paper, Z = Scissors)\n\
+             As outcomes: 12 (X = lose, Y = draw, Z = win)\n\
+             Best: 24 (X = Scissors, Y = Paper, Z = Rock)"
+        );
+        assert!(parse("D X", &game).is_err());
+    }
+
+    #[test]
+    fn test_missing_letters() {
+        // The letters keep their meaning when others don't show up
+        assert_eq!(part_one("A Y"), Some(8));
+        assert_eq!(part_two("A Y"), Some(4));
+        assert_eq!(part_one("B Z\nC Z"), Some(15));
+        assert_eq!(part_two("B Z\nC Z"), Some(16));
+        assert_eq!(part_one("A W"), None);
+    }
+
+    #[test]
+    fn test_lizard_spock() {
+        let game = Game::rock_paper_scissors_lizard_spock();
+        let [rock, spock, paper, lizard, scissors] = [0, 1, 2, 3, 4];
+        assert_eq!(game.outcome(paper, spock), Outcome::Win);
+        assert_eq!(game.outcome(lizard, spock), Outcome::Win);
+        assert_eq!(game.outcome(rock, scissors), Outcome::Win);
+        assert_eq!(game.outcome(spock, lizard), Outcome::Lose);
+        assert_eq!(game.response(scissors, Outcome::Win), spock);
+        let game = game.with_scores(&[0; 5], [0, 1, 2]).unwrap();
+        assert_eq!(game.score(rock, rock), 1);
+        assert!(Game::new(&["Rock", "Paper"]).is_err());
+        assert!(Game::rock_paper_scissors()
+            .with_scores(&[1, 2], [0, 3, 6])
+            .is_err());
+    }
+
+    #[test]
+    fn test_configure() {
+        let game = configure(Some("rpsls"), Some("5,4,3,2,1"), None).unwrap();
+        assert_eq!(game.moves.len(), 5);
+        assert_eq!(game.move_scores, vec![5, 4, 3, 2, 1]);
+        assert_eq!(game.outcome_scores, [0, 3, 6]);
+        // Scissors beats paper, and the draw is worth nothing
+        let game = configure(None, None, Some("1, 0, 2")).unwrap();
+        assert_eq!(game.score(2, 1), 3 + 2);
+        assert_eq!(game.score(1, 1), 2);
+        assert!(configure(Some("chess"), None, None).is_err());
+        assert!(configure(Some("rpsls"), Some("1,2,3"), None).is_err());
+        assert!(configure(None, None, Some("0,3")).is_err());
+        assert!(configure(None, Some("1,x,3"), None).is_err());
     }
 }
@@ -1,3 +1,4 @@
+use advent_of_code::helpers::parse;
 use regex::Regex;
 use std::collections::VecDeque;
 
@@ -6,9 +7,9 @@ fn parse_stacks(input: &str) -> Result<Vec<VecDeque<char>>, String> {
     containers at the top of the stack, and the last line is the integer ID of the stack.
 
     The input of stacks means each line is a fixed width, and since each conteainer takes up
-    3 character widths, and then has a space before the next container. This means we have to first learn
-    how many stacks there with the formula `length = 3 * num_containers + (num_containers - 1)`. If we solve this
-    equation for num_containers, we get `num_containers = (length + 1) / 4`.
+    3 character widths, and then has a space before the next container, every label sits
+    4 characters after the previous one, starting at the second character. The number of
+    stacks comes from the ID line, since the last stacks may have no containers at all.
 
     Example input is of this form:
         [D]
@@ -22,27 +23,17 @@ fn parse_stacks(input: &str) -> Result<Vec<VecDeque<char>>, String> {
     square brackets and are always a single uppercase character.
     */
 
-    // Collect the input lines in reverse
-    let lines = input.lines().rev().collect::<Vec<_>>();
-    let num_containers: usize = (lines[0].len() + 1) / 4;
-    let mut stacks: Vec<VecDeque<char>> = Vec::with_capacity(num_containers);
-    for _ in 0..num_containers {
-        stacks.push(VecDeque::new());
-    }
-    for line in lines[1..].iter() {
-        // Skipping the first character, generate an iterator that returns
-        // every 4th character, which is the container label
-        let chars = line[1..].chars().step_by(4);
-        for (stack_id, container) in chars.enumerate() {
-            match container {
-                ' ' => continue,
-                // match case to check if the container is a valid uppercase character
-                _ if container.is_ascii_uppercase() => stacks[stack_id].push_front(container),
-                _ => return Err(format!("Invalid container label: {}", container)),
-            }
-        }
-    }
-    Ok(stacks)
+    // The last line holds the stack IDs, which are just their positions
+    let (layout, ids) = input.rsplit_once('\n').ok_or("Missing stack IDs")?;
+    let mut stacks = parse::columns(layout, 1, 4, |container| match container {
+        ' ' => Ok(None),
+        // match case to check if the container is a valid uppercase character
+        _ if container.is_ascii_uppercase() => Ok(Some(container)),
+        _ => Err(format!("Invalid container label: {}", container)),
+    })
+    .map_err(|e| e.to_string())?;
+    stacks.resize_with(ids.split_whitespace().count(), Vec::new);
+    Ok(stacks.into_iter().map(VecDeque::from).collect())
 }
 
 struct Move {
@@ -64,10 +55,16 @@ fn parse_moves(input: &str) -> Result<Vec<Move>, String> {
     let mut moves = Vec::new();
     for line in input.lines() {
         if let Some(captures) = re.captures(line) {
+            // The digits can still be too many for a usize
+            let number = |name: &str| {
+                captures[name]
+                    .parse()
+                    .map_err(|e| format!("Invalid {} in move {:?}: {}", name, line, e))
+            };
             moves.push(Move {
-                number: captures["number"].parse().unwrap(),
-                source: captures["source"].parse().unwrap(),
-                target: captures["target"].parse().unwrap(),
+                number: number("number")?,
+                source: number("source")?,
+                target: number("target")?,
             });
         } else {
             return Err(format!("Invalid input move: {}", line));
@@ -76,10 +73,159 @@ fn parse_moves(input: &str) -> Result<Vec<Move>, String> {
     Ok(moves)
 }
 
-fn parse_part_one(input: &str) -> (Vec<VecDeque<char>>, Vec<Move>) {
+// What a crane does when it's told to move crates. The top of a stack is at
+// the front.
+trait Crane {
+    fn lift(&self, number: usize, source: &mut VecDeque<char>, target: &mut VecDeque<char>);
+}
+
+// Moves crates one at a time, so they end up in the opposite order
+struct CrateMover9000;
+
+impl Crane for CrateMover9000 {
+    fn lift(&self, number: usize, source: &mut VecDeque<char>, target: &mut VecDeque<char>) {
+        for container in source.drain(..number) {
+            target.push_front(container);
+        }
+    }
+}
+
+// Moves all the crates at once, so they keep their order
+struct CrateMover9001;
+
+impl Crane for CrateMover9001 {
+    fn lift(&self, number: usize, source: &mut VecDeque<char>, target: &mut VecDeque<char>) {
+        for container in source.drain(..number).rev() {
+            target.push_front(container);
+        }
+    }
+}
+
+// Runs the rearrangement procedure with a crane, one move at a time. Moving the
+// crates back with the same crane puts them back in their original order, so
+// any move can be undone and the stacks can be wound to any step and back.
+struct Simulator<C: Crane> {
+    crane: C,
+    stacks: Vec<VecDeque<char>>,
+    moves: Vec<Move>,
+    // How many of the moves have been made
+    done: usize,
+}
+
+impl<C: Crane> Simulator<C> {
+    fn new(crane: C, stacks: Vec<VecDeque<char>>, moves: Vec<Move>) -> Self {
+        Self {
+            crane,
+            stacks,
+            moves,
+            done: 0,
+        }
+    }
+
+    // Moves `number` crates, checking that the stacks exist and that there
+    // are enough crates on the source stack
+    fn lift(&mut self, number: usize, source: usize, target: usize) -> Result<(), String> {
+        for stack in [source, target] {
+            if stack == 0 || stack > self.stacks.len() {
+                return Err(format!("There is no stack {}", stack));
+            }
+        }
+        if source == target {
+            return Err(format!(
+                "Can't move crates from stack {} onto itself",
+                source
+            ));
+        }
+        let mut from = std::mem::take(&mut self.stacks[source - 1]);
+        if from.len() < number {
+            let error = format!("Stack {} only has {} crates", source, from.len());
+            self.stacks[source - 1] = from;
+            return Err(error);
+        }
+        self.crane
+            .lift(number, &mut from, &mut self.stacks[target - 1]);
+        self.stacks[source - 1] = from;
+        Ok(())
+    }
+
+    // Makes the next move, if there is one left
+    fn step(&mut self) -> Result<bool, String> {
+        let Some(move_) = self.moves.get(self.done) else {
+            return Ok(false);
+        };
+        let (number, source, target) = (move_.number, move_.source, move_.target);
+        self.lift(number, source, target)
+            .map_err(|e| format!("Move {}: {}", self.done + 1, e))?;
+        self.done += 1;
+        Ok(true)
+    }
+
+    // Takes back the last move, if any have been made
+    fn undo(&mut self) -> bool {
+        if self.done == 0 {
+            return false;
+        }
+        self.done -= 1;
+        let move_ = &self.moves[self.done];
+        let (number, source, target) = (move_.number, move_.source, move_.target);
+        // The move went through, so the crates it moved are there to take back
+        self.lift(number, target, source).unwrap();
+        true
+    }
+
+    // Winds the stacks forwards or backwards to how they are after `step` moves
+    fn seek(&mut self, step: usize) -> Result<(), String> {
+        if step > self.moves.len() {
+            return Err(format!("There are only {} moves", self.moves.len()));
+        }
+        while self.done > step {
+            self.undo();
+        }
+        while self.done < step {
+            self.step()?;
+        }
+        Ok(())
+    }
+
+    fn run(&mut self) -> Result<(), String> {
+        self.seek(self.moves.len())
+    }
+
+    // The crate at the top of every stack that has one
+    fn tops(&self) -> String {
+        self.stacks
+            .iter()
+            .filter_map(|stack| stack.front())
+            .collect()
+    }
+
+    // The stacks drawn the way the puzzle input does
+    fn render(&self) -> String {
+        let height = self.stacks.iter().map(VecDeque::len).max().unwrap_or(0);
+        let mut rows: Vec<String> = (0..height)
+            .map(|row| {
+                self.stacks
+                    .iter()
+                    .map(|stack| match (row + stack.len()).checked_sub(height) {
+                        Some(i) => format!("[{}]", stack[i]),
+                        None => "   ".to_string(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .collect();
+        let ids: Vec<String> = (1..=self.stacks.len())
+            .map(|id| format!(" {} ", id))
+            .collect();
+        rows.push(ids.join(" "));
+        rows.join("\n")
+    }
+}
+
+fn parse_input(input: &str) -> Result<(Vec<VecDeque<char>>, Vec<Move>), String> {
     /* Parse the input of stacks and moves using the associated helper functions.
 
-    There are two newlines between the stacks and the moves, so we can split the input on that to get the two parts.
+    There is a blank line between the stacks and the moves, so we can split the input on that to get the two parts.
 
     Example input is of this form:
         [D]
@@ -93,54 +239,56 @@ fn parse_part_one(input: &str) -> (Vec<VecDeque<char>>, Vec<Move>) {
     move 1 from 1 to 2
     <EOF>
     */
-    let mut parts = input.split("\n\n");
-    let stacks = parse_stacks(parts.next().unwrap()).unwrap();
-    let moves = parse_moves(parts.next().unwrap()).unwrap();
-    (stacks, moves)
+    let parts = parse::blocks(input);
+    if parts.len() != 2 {
+        return Err("Expected the stacks and the moves, separated by a blank line".to_string());
+    }
+    Ok((parse_stacks(parts[0])?, parse_moves(parts[1])?))
+}
+
+// The stacks after the first `step` moves, drawn the way the input does
+fn draw_step(crane: impl Crane, input: &str, step: usize) -> Result<String, String> {
+    let (stacks, moves) = parse_input(input)?;
+    let mut simulator = Simulator::new(crane, stacks, moves);
+    simulator.seek(step)?;
+    Ok(simulator.render())
 }
 
 pub fn part_one(input: &str) -> Option<String> {
     // Given the puzzle input, we need to move the containers from one stack to the other
     // and return the container at the top of each stack
-    let (mut stacks, moves) = parse_part_one(input);
-    for move_ in moves {
-        for _ in 0..move_.number {
-            let container = stacks[move_.source - 1].pop_front().unwrap();
-            stacks[move_.target - 1].push_front(container);
-        }
-    }
-    let mut labels = Vec::new();
-    for stack in stacks {
-        labels.push(stack[0]);
-    }
-    Some(labels.iter().collect())
+    let (stacks, moves) = parse_input(input).ok()?;
+    let mut simulator = Simulator::new(CrateMover9000, stacks, moves);
+    simulator.run().ok()?;
+    Some(simulator.tops())
 }
 
 pub fn part_two(input: &str) -> Option<String> {
     // Apply the same logic as `part_one`, except this time time we make a move
-    // the containers on the stack stay in the same order, so rather than popping
-    // and pushing each individual container, we need to collect the number of containers
-    // in their original order then stack them in that same order on top of the new stack
-    let (mut stacks, moves) = parse_part_one(input);
-    for move_ in moves {
-        let mut containers = Vec::new();
-        for _ in 0..move_.number {
-            let container = stacks[move_.source - 1].pop_front().unwrap();
-            containers.push(container);
-        }
-        for container in containers.into_iter().rev() {
-            stacks[move_.target - 1].push_front(container);
-        }
-    }
-    let mut labels = Vec::new();
-    for stack in stacks {
-        labels.push(stack[0]);
-    }
-    Some(labels.iter().collect())
+    // the containers on the stack stay in the same order, which is down to the crane
+    let (stacks, moves) = parse_input(input).ok()?;
+    let mut simulator = Simulator::new(CrateMover9001, stacks, moves);
+    simulator.run().ok()?;
+    Some(simulator.tops())
 }
 
 fn main() {
     let input = &advent_of_code::read_file("inputs", 5);
+    // `cargo solve 05 -- --step 3` draws the stacks after the first 3 moves,
+    // with both cranes
+    let mut args = pico_args::Arguments::from_env();
+    let step: Option<usize> = args.opt_value_from_str("--step").unwrap();
+    if let Some(step) = step {
+        for (name, drawing) in [
+            ("CrateMover 9000", draw_step(CrateMover9000, input, step)),
+            ("CrateMover 9001", draw_step(CrateMover9001, input, step)),
+        ] {
+            match drawing {
+                Ok(drawing) => println!("{}:\n{}\n", name, drawing),
+                Err(error) => eprintln!("{}: {}\n", name, error),
+            }
+        }
+    }
     advent_of_code::solve!(1, part_one, input);
     advent_of_code::solve!(2, part_two, input);
 }
@@ -160,4 +308,62 @@ mod tests {
         let input = advent_of_code::read_file("examples", 5);
         assert_eq!(part_two(&input), Some("MCD".to_string()));
     }
+
+    #[test]
+    fn test_undo_and_render() {
+        let input = advent_of_code::read_file("examples", 5);
+        let (stacks, moves) = parse_input(&input).unwrap();
+        let mut simulator = Simulator::new(CrateMover9000, stacks, moves);
+        let start = simulator.render();
+        assert_eq!(start, parse::blocks(&input)[0]);
+        simulator.seek(2).unwrap();
+        assert_eq!(
+            simulator.render(),
+            "        [Z]\n        [N]\n    [C] [D]\n    [M] [P]\n 1   2   3 "
+        );
+        simulator.run().unwrap();
+        assert!(!simulator.step().unwrap());
+        while simulator.undo() {}
+        assert_eq!(simulator.render(), start);
+        simulator.run().unwrap();
+        assert_eq!(simulator.tops(), "CMZ");
+    }
+
+    #[test]
+    fn test_invalid_moves() {
+        let stacks = parse_stacks("[A]    \n 1   2 ").unwrap();
+        let moves = vec![
+            Move {
+                number: 1,
+                source: 1,
+                target: 2,
+            },
+            Move {
+                number: 2,
+                source: 2,
+                target: 3,
+            },
+            Move {
+                number: 2,
+                source: 2,
+                target: 1,
+            },
+        ];
+        let mut simulator = Simulator::new(CrateMover9001, stacks, moves);
+        assert_eq!(simulator.step(), Ok(true));
+        assert_eq!(
+            simulator.step(),
+            Err("Move 2: There is no stack 3".to_string())
+        );
+        simulator.moves.remove(1);
+        assert_eq!(
+            simulator.step(),
+            Err("Move 2: Stack 2 only has 1 crates".to_string())
+        );
+        assert_eq!(simulator.tops(), "A");
+        assert!(parse_moves("move 99999999999999999999 from 1 to 2").is_err());
+        let input = advent_of_code::read_file("examples", 5);
+        assert!(draw_step(CrateMover9000, &input, 5).is_err());
+        assert!(draw_step(CrateMover9000, &input, 4).is_ok());
+    }
 }
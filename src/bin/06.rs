@@ -1,38 +1,19 @@
-use std::collections::{HashSet, VecDeque};
+use advent_of_code::helpers::marker;
+
+// The first place where the last `width` characters were all different
+fn first_marker(input: &str, width: usize) -> Option<u32> {
+    let position = marker::first(input.as_bytes(), width).ok()??;
+    Some(position as u32)
+}
 
 pub fn part_one(input: &str) -> Option<u32> {
-    // Iterate through the input, storing it 4 characters at a time. Store
-    // this buffer in a Vec or VecDeque. We need to check if the current 4 characters
-    // in the buffer are unique. If they are unique, we want to return the index
-    // when that occurred.
-    let mut buffer = VecDeque::new();
-    for (index, character) in input.chars().enumerate() {
-        buffer.push_back(character);
-        if buffer.len() == 4 {
-            let set: HashSet<&char> = HashSet::from_iter(buffer.iter());
-            if set.len() == 4 {
-                return Some((index as u32) + 1);
-            }
-            buffer.pop_front();
-        }
-    }
-    None
+    // The start-of-packet marker is 4 different characters
+    first_marker(input, 4)
 }
 
 pub fn part_two(input: &str) -> Option<u32> {
     // same as pt 1, but check for 14 unique characters
-    let mut buffer = VecDeque::new();
-    for (index, character) in input.chars().enumerate() {
-        buffer.push_back(character);
-        if buffer.len() == 14 {
-            let set: HashSet<&char> = HashSet::from_iter(buffer.iter());
-            if set.len() == 14 {
-                return Some((index as u32) + 1);
-            }
-            buffer.pop_front();
-        }
-    }
-    None
+    first_marker(input, 14)
 }
 
 fn main() {
@@ -48,7 +29,7 @@ mod tests {
     #[test]
     fn test_part_one() {
         let input = advent_of_code::read_file("examples", 6);
-        assert_eq!(part_one(&input), Some(5));
+        assert_eq!(part_one(&input), Some(7));
     }
 
     #[test]
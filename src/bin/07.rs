@@ -1,5 +1,5 @@
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 #[derive(Debug)]
 enum Line {
@@ -14,126 +14,115 @@ impl FromStr for Line {
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let parts = s.split_whitespace().collect::<Vec<&str>>();
-        match parts[0] {
-            "$" => match parts[1] {
-                "cd" => Ok(Line::Cd(parts[2].to_string())),
-                "ls" => Ok(Line::Ls),
-                _ => Err("Invalid command".to_string()),
-            },
-            "dir" => Ok(Line::Dir(parts[1].to_string())),
+        match parts[..] {
+            ["$", "cd", dir] => Ok(Line::Cd(dir.to_string())),
+            ["$", "ls"] => Ok(Line::Ls),
+            ["dir", dir] => Ok(Line::Dir(dir.to_string())),
             // Match a string that's all digits
-            digits if digits.chars().all(|c| c.is_digit(10)) => {
-                let size = digits.parse::<u32>().unwrap();
-                Ok(Line::File(size, parts[1].to_string()))
+            [digits, file] if digits.chars().all(|c| c.is_ascii_digit()) => {
+                let size = digits.parse::<u32>().map_err(|e| e.to_string())?;
+                Ok(Line::File(size, file.to_string()))
             }
-            _ => Err("Invalid command".to_string()),
+            _ => Err(format!("Invalid line: {}", s)),
         }
     }
 }
 
-pub fn part_one(input: &str) -> Option<u32> {
-    let lines = input.lines().skip(1).map(|l| l.parse::<Line>().unwrap());
-    let mut sizes: HashMap<PathBuf, u32> = HashMap::new();
-    let root = PathBuf::from_str("/").unwrap();
-    let mut current_dir: PathBuf = root;
-    sizes.entry(current_dir.clone()).or_insert(0);
-    for line in lines {
-        match line {
-            Line::Cd(dir) => match dir.as_str() {
-                ".." => {
-                    let new_dir: PathBuf = current_dir.parent().unwrap().to_path_buf();
-                    current_dir = new_dir;
+// Everything we've learned about the filesystem from a terminal transcript.
+// Files are keyed by name within their directory, so listing the same
+// directory twice doesn't count its files twice.
+#[derive(Debug, Default)]
+struct Snapshot {
+    dirs: HashMap<PathBuf, HashMap<String, u32>>,
+}
+
+impl Snapshot {
+    fn root() -> PathBuf {
+        PathBuf::from("/")
+    }
+
+    fn replay(input: &str) -> Result<Self, String> {
+        let mut snapshot = Snapshot::default();
+        let mut current_dir = Snapshot::root();
+        snapshot.dirs.entry(current_dir.clone()).or_default();
+        for line in input.lines() {
+            match line.parse::<Line>()? {
+                Line::Cd(dir) => match dir.as_str() {
+                    "/" => current_dir = Snapshot::root(),
+                    ".." => {
+                        if !current_dir.pop() {
+                            return Err("Can't go above the root directory".to_string());
+                        }
+                    }
+                    _ => current_dir.push(dir),
+                },
+                Line::Ls => {
+                    // We don't need to do anything on the line we run the `ls` command
                 }
-                _ => {
-                    let new_dir = current_dir.join(dir);
-                    current_dir = new_dir;
+                Line::Dir(dir) => {
+                    // remember the directory, even if we never `cd` into it
+                    snapshot.dirs.entry(current_dir.join(dir)).or_default();
+                }
+                Line::File(size, file) => {
+                    snapshot
+                        .dirs
+                        .entry(current_dir.clone())
+                        .or_default()
+                        .insert(file, size);
                 }
-            },
-            Line::Ls => {
-                // We don't need to do anything on the line we run the `ls` command
-            }
-            Line::Dir(dir) => {
-                // assume we always `cd` into a dir eventually, so we don't need to do anything when we see this command
-            }
-            Line::File(size, file) => {
-                *sizes.entry(current_dir.clone()).or_insert(0) += size;
             }
+            // `cd` may take us somewhere we haven't seen in a listing yet
+            snapshot.dirs.entry(current_dir.clone()).or_default();
         }
+        Ok(snapshot)
     }
-    let mut recursive_sizes: HashMap<PathBuf, u32> = HashMap::new();
-    for (path, size) in sizes.iter() {
-        // We want to add the size of the current dir to the size of all of its parents
-        for path in path.ancestors() {
-            *recursive_sizes.entry(path.to_path_buf()).or_insert(0) += size;
+
+    // The total size of every directory, including everything below it
+    fn dir_sizes(&self) -> HashMap<&Path, u32> {
+        let mut recursive_sizes: HashMap<&Path, u32> = HashMap::new();
+        for (path, files) in self.dirs.iter() {
+            let size: u32 = files.values().sum();
+            // We want to add the size of the current dir to the size of all of its parents
+            for path in path.ancestors() {
+                *recursive_sizes.entry(path).or_insert(0) += size;
+            }
         }
+        recursive_sizes
     }
 
-    // Find entries that have a size of less than 100000, and sum their sizes
-    let mut total = 0;
-    for (path, size) in recursive_sizes.iter() {
-        if *size < 100000 {
-            total += size;
-        }
+    fn used(&self) -> u32 {
+        self.dir_sizes()[Snapshot::root().as_path()]
+    }
+
+    // Sizes of the directories with a total size of at most `limit`
+    fn dirs_at_most(&self, limit: u32) -> Vec<u32> {
+        self.dir_sizes()
+            .into_values()
+            .filter(|size| *size <= limit)
+            .collect()
+    }
+
+    // The size of the smallest directory we could delete to have at least
+    // `needed` bytes free on a disk of `total` bytes
+    fn smallest_freeing(&self, total: u32, needed: u32) -> Option<u32> {
+        let need_to_free = needed.saturating_sub(total.checked_sub(self.used())?);
+        self.dir_sizes()
+            .into_values()
+            .filter(|size| *size >= need_to_free)
+            .min()
     }
+}
 
-    // dbg!(recursive_sizes);
-    Some(total)
+pub fn part_one(input: &str) -> Option<u32> {
+    let snapshot = Snapshot::replay(input).ok()?;
+    Some(snapshot.dirs_at_most(100000).iter().sum())
 }
 
 pub fn part_two(input: &str) -> Option<u32> {
     const TOTAL_SPACE: u32 = 70000000;
     const NEEDED_UNUSED: u32 = 30000000;
-    let lines = input.lines().skip(1).map(|l| l.parse::<Line>().unwrap());
-    let mut sizes: HashMap<PathBuf, u32> = HashMap::new();
-    let root = PathBuf::from_str("/").unwrap();
-    let mut current_dir: PathBuf = root.clone();
-    sizes.entry(current_dir.clone()).or_insert(0);
-    for line in lines {
-        match line {
-            Line::Cd(dir) => match dir.as_str() {
-                ".." => {
-                    let new_dir: PathBuf = current_dir.parent().unwrap().to_path_buf();
-                    current_dir = new_dir;
-                }
-                _ => {
-                    let new_dir = current_dir.join(dir);
-                    current_dir = new_dir;
-                }
-            },
-            Line::Ls => {
-                // We don't need to do anything on the line we run the `ls` command
-            }
-            Line::Dir(dir) => {
-                // assume we always `cd` into a dir eventually, so we don't need to do anything when we see this command
-            }
-            Line::File(size, file) => {
-                *sizes.entry(current_dir.clone()).or_insert(0) += size;
-            }
-        }
-    }
-    let mut recursive_sizes: HashMap<PathBuf, u32> = HashMap::new();
-    for (path, size) in sizes.iter() {
-        // We want to add the size of the current dir to the size of all of its parents
-        for path in path.ancestors() {
-            *recursive_sizes.entry(path.to_path_buf()).or_insert(0) += size;
-        }
-    }
-
-    let need_to_free = NEEDED_UNUSED - (TOTAL_SPACE - recursive_sizes.get(&root).unwrap());
-    // Collect the paths and sort them by size
-    let mut paths_by_size = recursive_sizes
-        .iter()
-        .map(|(path, size)| (path, size))
-        .collect::<Vec<(&PathBuf, &u32)>>();
-    paths_by_size.sort_by(|(_, size1), (_, size2)| size1.cmp(size2));
-    dbg!(&paths_by_size);
-    let (_delete_path, delete_size) = paths_by_size
-        .iter()
-        .find(|(_, size)| size > &&need_to_free)
-        .unwrap();
-
-    // Return the size of the path we need to delete
-    Some(**delete_size)
+    let snapshot = Snapshot::replay(input).ok()?;
+    snapshot.smallest_freeing(TOTAL_SPACE, NEEDED_UNUSED)
 }
 
 fn main() {
@@ -157,4 +146,17 @@ mod tests {
         let input = advent_of_code::read_file("examples", 7);
         assert_eq!(part_two(&input), Some(24933642));
     }
+
+    #[test]
+    fn test_replay_repeated_listing() {
+        // lists the root twice, and jumps back to it with `cd /`
+        let input = "$ cd /\n$ ls\ndir a\n10 b\n$ cd a\n$ ls\n5 c\n$ cd /\n$ ls\ndir a\n10 b\n";
+        let snapshot = Snapshot::replay(input).unwrap();
+        assert_eq!(snapshot.used(), 15);
+        assert_eq!(snapshot.dirs_at_most(5), vec![5]);
+        assert_eq!(snapshot.smallest_freeing(30, 20), Some(5));
+        // more is used than fits on the disk
+        assert_eq!(snapshot.smallest_freeing(10, 5), None);
+        assert!(Snapshot::replay("$ cd ..").is_err());
+    }
 }
@@ -1,110 +1,61 @@
-// This is an old, convoluted solution I tried to work at initially for Day 7
-// I'm keeping it here for posterity, but it's not used in the final solution
+// An alternative solution for Day 7. Instead of only tracking directory sizes
+// by path (see 07.rs), this replays the terminal transcript into a small
+// in-memory filesystem stored in an arena tree, which we can then walk, query
+// and print like a real one.
 
 use std::str::FromStr;
 
 #[derive(Debug)]
-struct Node<T>
-where
-    T: PartialEq,
-{
-    idx: usize,
+struct Node<T> {
     val: T,
     parent: Option<usize>,
     children: Vec<usize>,
 }
 
-impl<T> Node<T>
-where
-    T: PartialEq,
-{
-    fn new(idx: usize, val: T) -> Self {
+impl<T> Node<T> {
+    fn new(val: T, parent: Option<usize>) -> Self {
         Self {
-            idx,
             val,
-            parent: None,
+            parent,
             children: vec![],
         }
     }
 }
 
-#[derive(Debug, Default)]
-struct ArenaTree<T>
-where
-    T: PartialEq,
-{
+// Nodes refer to each other by their index in the arena. A node is always
+// added after its parent, so parents have smaller indexes than their children.
+#[derive(Debug)]
+struct ArenaTree<T> {
     arena: Vec<Node<T>>,
 }
 
-impl<T> ArenaTree<T>
-where
-    T: PartialEq,
-{
-    fn node(&mut self, val: T) -> usize {
-        //first see if it exists
-        for node in &self.arena {
-            if node.val == val {
-                return node.idx;
-            }
-        }
-        // Otherwise, add new node
+impl<T> Default for ArenaTree<T> {
+    fn default() -> Self {
+        Self { arena: vec![] }
+    }
+}
+
+impl<T> ArenaTree<T> {
+    fn add(&mut self, val: T, parent: Option<usize>) -> usize {
         let idx = self.arena.len();
-        self.arena.push(Node::new(idx, val));
+        self.arena.push(Node::new(val, parent));
+        if let Some(parent) = parent {
+            self.arena[parent].children.push(idx);
+        }
         idx
     }
+    fn get(&self, idx: usize) -> &Node<T> {
+        &self.arena[idx]
+    }
     fn size(&self) -> usize {
         self.arena.len()
     }
-    fn edges(&self) -> usize {
-        self.arena
-            .iter()
-            .fold(0, |acc, node| acc + node.children.len())
-    }
-
     fn depth(&self, idx: usize) -> usize {
         match self.arena[idx].parent {
             Some(id) => 1 + self.depth(id),
             None => 0,
         }
     }
-    fn depth_to_target(&self, idx: usize, target: &T) -> Option<usize> {
-        // are we here?  If so, Some(0)
-        if target == &self.arena[idx].val {
-            return Some(0);
-        }
-
-        // If not, try all children
-        for p in &self.arena[idx].children {
-            if let Some(x) = self.depth_to_target(*p, &target) {
-                return Some(1 + x);
-            }
-        }
-        // If it cant be found, return None
-        None
-    }
-    fn distance_between(&mut self, from: T, target: T) -> usize {
-        // If it's not in the tree, this will add a new unconnected node
-        // the final function will still return None
-        let start_node = self.node(from);
-        let mut ret = 0;
-        // Start traversal
-        let mut trav = &self.arena[start_node];
-        // Explore all children, then hop up one
-        while let Some(inner) = trav.parent {
-            if let Some(x) = self.depth_to_target(inner, &target) {
-                ret += x;
-                break;
-            }
-            trav = &self.arena[inner];
-            ret += 1;
-        }
-        // don't go all the way to target, just orbit
-        if ret > 0 {
-            ret - 1
-        } else {
-            ret
-        }
-    }
 }
 
 enum Line {
@@ -116,18 +67,14 @@ impl FromStr for Line {
     type Err = String;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut split_input = s.split_whitespace();
-        match split_input.next() {
-            Some("$") => Ok(Line::Command(s.parse().unwrap())),
-            Some("dir") => Ok(Line::Object(s.parse().unwrap())),
-            // Idiomatically and concicely check if the first character is a digit
-            Some(s) if s.chars().next().unwrap().is_digit(10) => {
-                Ok(Line::Object(s.parse().unwrap()))
-            }
-            _ => Err("Invalid line".to_string()),
+        match s.split_whitespace().next() {
+            Some("$") => Ok(Line::Command(s.parse()?)),
+            Some(_) => Ok(Line::Object(s.parse()?)),
+            None => Err("Empty line".to_string()),
         }
     }
 }
+
 enum Command {
     CD(String),
     LS,
@@ -138,20 +85,17 @@ impl FromStr for Command {
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let mut parts = s[1..].split_whitespace();
-        match parts.next() {
-            Some("cd") => Ok(Command::CD(parts.next().unwrap().to_string())),
-            Some("ls") => Ok(Command::LS),
-            _ => Err("Invalid command".to_string()),
+        match (parts.next(), parts.next()) {
+            (Some("cd"), Some(target)) => Ok(Command::CD(target.to_string())),
+            (Some("ls"), None) => Ok(Command::LS),
+            _ => Err(format!("Invalid command: {}", s)),
         }
     }
 }
 
-#[derive(Debug, PartialEq, Default)]
-
+#[derive(Debug, PartialEq)]
 struct Dir {
     name: String,
-    parent: Option<Box<Dir>>,
-    children: Vec<Object>,
 }
 
 // directories appear as 'dir <name>'
@@ -161,18 +105,16 @@ impl FromStr for Dir {
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let mut parts = s.split_whitespace();
-        match parts.next() {
-            Some("dir") => Ok(Dir {
-                name: parts.next().unwrap().to_string(),
-                parent: None,
-                children: Vec::new(),
+        match (parts.next(), parts.next()) {
+            (Some("dir"), Some(name)) => Ok(Dir {
+                name: name.to_string(),
             }),
-            _ => Err("Invalid dir command".to_string()),
+            _ => Err(format!("Invalid dir: {}", s)),
         }
     }
 }
 
-#[derive(Debug, PartialEq, Default)]
+#[derive(Debug, PartialEq)]
 struct File {
     name: String,
     size: u32,
@@ -184,25 +126,28 @@ impl FromStr for File {
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let parts = s.split_whitespace().collect::<Vec<&str>>();
-        dbg!(&parts);
         if parts.len() == 2 {
-            let size = parts[0].parse::<u32>().unwrap();
+            let size = parts[0].parse::<u32>().map_err(|e| e.to_string())?;
             let name = parts[1].to_string();
             Ok(File { name, size })
         } else {
-            Err("Invalid file command".to_string())
+            Err(format!("Invalid file: {}", s))
         }
     }
 }
+
 #[derive(Debug, PartialEq)]
 enum Object {
     Dir(Dir),
     File(File),
 }
 
-impl Default for Object {
-    fn default() -> Self {
-        Object::Dir(Dir::default())
+impl Object {
+    fn name(&self) -> &str {
+        match self {
+            Object::Dir(dir) => &dir.name,
+            Object::File(file) => &file.name,
+        }
     }
 }
 
@@ -211,12 +156,169 @@ impl FromStr for Object {
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s.split_whitespace().next() {
-            Some("dir") => Ok(Object::Dir(s.parse().unwrap())),
-            Some(s) if s.chars().next().unwrap().is_digit(10) => {
-                Ok(Object::File(s.parse().unwrap()))
+            Some("dir") => Ok(Object::Dir(s.parse()?)),
+            Some(first) if first.chars().all(|c| c.is_ascii_digit()) => {
+                Ok(Object::File(s.parse()?))
+            }
+            _ => Err(format!("Invalid object: {}", s)),
+        }
+    }
+}
+
+struct FileSystem {
+    tree: ArenaTree<Object>,
+    root: usize,
+    current_dir: usize,
+}
+
+impl FileSystem {
+    fn new() -> Self {
+        let mut tree = ArenaTree::default();
+        let root = tree.add(
+            Object::Dir(Dir {
+                name: "/".to_string(),
+            }),
+            None,
+        );
+        FileSystem {
+            tree,
+            root,
+            current_dir: root,
+        }
+    }
+
+    fn child(&self, dir: usize, name: &str) -> Option<usize> {
+        self.tree
+            .get(dir)
+            .children
+            .iter()
+            .copied()
+            .find(|child| self.tree.get(*child).val.name() == name)
+    }
+
+    // Adds an object to the current directory. Listing the same directory
+    // twice doesn't add anything new.
+    fn add(&mut self, object: Object) -> usize {
+        match self.child(self.current_dir, object.name()) {
+            Some(existing) => existing,
+            None => self.tree.add(object, Some(self.current_dir)),
+        }
+    }
+
+    fn cd(&mut self, target: &str) -> Result<(), String> {
+        let dir = match target {
+            "/" => self.root,
+            ".." => self
+                .tree
+                .get(self.current_dir)
+                .parent
+                .ok_or("Can't go above the root directory")?,
+            // we might `cd` into a directory before we've seen it in an `ls`
+            name => self.add(Object::Dir(Dir {
+                name: name.to_string(),
+            })),
+        };
+        if let Object::File(file) = &self.tree.get(dir).val {
+            return Err(format!("Not a directory: {}", file.name));
+        }
+        self.current_dir = dir;
+        Ok(())
+    }
+
+    // Finds a node from an absolute path like `/a/e`
+    fn lookup(&self, path: &str) -> Option<usize> {
+        path.split('/')
+            .filter(|part| !part.is_empty())
+            .try_fold(self.root, |dir, name| self.child(dir, name))
+    }
+
+    fn path(&self, idx: usize) -> String {
+        let mut names = vec![];
+        let mut node = idx;
+        while let Some(parent) = self.tree.get(node).parent {
+            names.push(self.tree.get(node).val.name());
+            node = parent;
+        }
+        names.reverse();
+        format!("/{}", names.join("/"))
+    }
+
+    // Total size of every node, including everything below it. Because
+    // children always come after their parents in the arena, a single pass
+    // from the back adds every size into its parent before the parent is read.
+    fn sizes(&self) -> Vec<u32> {
+        let mut sizes: Vec<u32> = self
+            .tree
+            .arena
+            .iter()
+            .map(|node| match &node.val {
+                Object::File(file) => file.size,
+                Object::Dir(_) => 0,
+            })
+            .collect();
+        for idx in (0..self.tree.size()).rev() {
+            if let Some(parent) = self.tree.get(idx).parent {
+                sizes[parent] += sizes[idx];
+            }
+        }
+        sizes
+    }
+
+    // Every directory and its total size
+    fn dir_sizes(&self) -> Vec<(usize, u32)> {
+        self.sizes()
+            .into_iter()
+            .enumerate()
+            .filter(|(idx, _)| matches!(self.tree.get(*idx).val, Object::Dir(_)))
+            .collect()
+    }
+
+    fn is_within(&self, idx: usize, dir: usize) -> bool {
+        let mut node = Some(idx);
+        while let Some(current) = node {
+            if current == dir {
+                return true;
+            }
+            node = self.tree.get(current).parent;
+        }
+        false
+    }
+
+    // Like `du`: one line per directory below `dir` with its total size and path
+    fn du(&self, dir: usize) -> String {
+        self.dir_sizes()
+            .iter()
+            .filter(|(idx, _)| self.is_within(*idx, dir))
+            .map(|(idx, size)| format!("{}\t{}\n", size, self.path(*idx)))
+            .collect()
+    }
+
+    // The same layout the puzzle uses to describe the filesystem
+    fn tree(&self) -> String {
+        let mut output = String::new();
+        let mut stack = vec![self.root];
+        while let Some(idx) = stack.pop() {
+            let node = self.tree.get(idx);
+            let indent = "  ".repeat(self.tree.depth(idx));
+            match &node.val {
+                Object::Dir(dir) => output.push_str(&format!("{}- {} (dir)\n", indent, dir.name)),
+                Object::File(file) => output.push_str(&format!(
+                    "{}- {} (file, size={})\n",
+                    indent, file.name, file.size
+                )),
             }
-            _ => Err("Invalid object".to_string()),
+            let mut children = node.children.clone();
+            // pop from the back, so sort in reverse to print in name order
+            children.sort_by(|a, b| {
+                self.tree
+                    .get(*b)
+                    .val
+                    .name()
+                    .cmp(self.tree.get(*a).val.name())
+            });
+            stack.extend(children);
         }
+        output
     }
 }
 
@@ -224,8 +326,7 @@ impl FromStr for Object {
 determine the file and folder structure of our filesystem by parsing the lines and
 storing the correct objects. We need to do this by keeping track of the current directory,
 which can change with a 'cd' command, and then document which files or directories are in the current
-directory when we see an 'ls' command. We should store this in a tree-like data structure that is
-returned from this function.
+directory when we see an 'ls' command.
 
 For example, here's some input:
 
@@ -253,47 +354,77 @@ $ ls
 5626152 d.ext
 7214296 k
 <EOF>
-
-Return these objects in a tree-like data structure so that we can traverse it
-and find the size of the files in each directory.
 */
-fn parse_input(input: &str) {
-    let root = Object::Dir(Dir {
-        name: "/".to_string(),
-        parent: None,
-        children: Vec::new(),
-    });
-    let mut current_dir = &root;
-    let mut tree: ArenaTree<Object> = ArenaTree::default();
-    let root_node = tree.node(root);
-    for line in input.lines().skip(1) {
-        match line.parse::<Line>() {
-            Ok(Line::Command(Command::CD(dir))) => {
-                println!("cd {}", dir)
+impl FromStr for FileSystem {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let mut fs = FileSystem::new();
+        for line in input.lines() {
+            match line.parse::<Line>()? {
+                Line::Command(Command::CD(dir)) => fs.cd(&dir)?,
+                // the listing is on the following lines
+                Line::Command(Command::LS) => {}
+                Line::Object(object) => {
+                    fs.add(object);
+                }
             }
-            Ok(Line::Command(Command::LS)) => println!("ls"),
-            Ok(Line::Object(object)) => println!("{:?}", object),
-            Err(e) => panic!("Invalid line: {}", e),
         }
+        Ok(fs)
     }
 }
 
 pub fn part_one(input: &str) -> Option<u32> {
-    let tree = parse_input(input);
-    dbg!(tree);
-    None
+    let fs: FileSystem = input.parse().ok()?;
+    Some(
+        fs.dir_sizes()
+            .iter()
+            .map(|(_, size)| size)
+            .filter(|size| **size <= 100000)
+            .sum(),
+    )
 }
 
 pub fn part_two(input: &str) -> Option<u32> {
-    None
+    const TOTAL_SPACE: u32 = 70000000;
+    const NEEDED_UNUSED: u32 = 30000000;
+    let fs: FileSystem = input.parse().ok()?;
+    let used = fs.sizes()[fs.root];
+    let need_to_free = NEEDED_UNUSED.saturating_sub(TOTAL_SPACE.checked_sub(used)?);
+    fs.dir_sizes()
+        .into_iter()
+        .map(|(_, size)| size)
+        .filter(|size| *size >= need_to_free)
+        .min()
 }
 
 fn main() {
     let input = &advent_of_code::read_file("inputs", 7);
+    // Look around the filesystem with `cargo solve 07b -- --tree` or `-- --du /some/dir`
+    let mut args = pico_args::Arguments::from_env();
+    let du: Option<String> = args.opt_value_from_str("--du").unwrap();
+    if args.contains("--tree") || du.is_some() {
+        let fs: FileSystem = input.parse().unwrap();
+        if let Some(path) = du {
+            match fs.lookup(&path) {
+                Some(dir) => print!("{}", fs.du(dir)),
+                None => eprintln!("No such file or directory: {}", path),
+            }
+        } else {
+            print!("{}", fs.tree());
+        }
+        return;
+    }
     advent_of_code::solve!(1, part_one, input);
-    // advent_of_code::solve!(2, part_two, input);
+    advent_of_code::solve!(2, part_two, input);
 }
 
+// the main solution, so we can check both agree
+#[cfg(test)]
+#[allow(dead_code)]
+#[path = "07.rs"]
+mod day_07;
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -301,12 +432,51 @@ mod tests {
     #[test]
     fn test_part_one() {
         let input = advent_of_code::read_file("examples", 7);
-        assert_eq!(part_one(&input), ());
+        assert_eq!(part_one(&input), Some(95437));
     }
 
     #[test]
     fn test_part_two() {
         let input = advent_of_code::read_file("examples", 7);
-        assert_eq!(part_two(&input), None);
+        assert_eq!(part_two(&input), Some(24933642));
+    }
+
+    #[test]
+    fn test_filesystem() {
+        let input = advent_of_code::read_file("examples", 7);
+        let fs: FileSystem = input.parse().unwrap();
+        let e = fs.lookup("/a/e").unwrap();
+        assert_eq!(fs.path(e), "/a/e");
+        assert_eq!(fs.sizes()[e], 584);
+        assert_eq!(fs.lookup("/a/x"), None);
+        assert_eq!(fs.du(fs.lookup("/a").unwrap()), "94853\t/a\n584\t/a/e\n");
+        assert!(fs.tree().starts_with(
+            "- / (dir)\n  - a (dir)\n    - e (dir)\n      - i (file, size=584)\n    - f (file, size=29116)\n"
+        ));
+    }
+
+    #[test]
+    fn test_repeated_listing() {
+        let input = "$ cd /\n$ ls\n10 a\ndir b\n$ cd b\n$ ls\n5 c\n$ cd /\n$ ls\n10 a\ndir b\n";
+        let fs: FileSystem = input.parse().unwrap();
+        assert_eq!(fs.sizes()[fs.root], 15);
+        assert_eq!(fs.tree.size(), 4);
+    }
+
+    #[test]
+    fn test_overfull_disk() {
+        let input = "$ cd /\n$ ls\n70000001 a\n";
+        assert_eq!(part_two(input), None);
+    }
+
+    #[test]
+    fn test_matches_day_07() {
+        let example = advent_of_code::read_file("examples", 7);
+        let repeated = "$ cd /\n$ ls\n10 a\ndir b\n$ cd b\n$ ls\n5 c\n$ cd /\n$ ls\n10 a\ndir b\n";
+        for input in [example.as_str(), repeated] {
+            assert_eq!(part_one(input), day_07::part_one(input));
+            assert_eq!(part_two(input), day_07::part_two(input));
+        }
+        assert_eq!(part_one(repeated), Some(20));
     }
 }
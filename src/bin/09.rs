@@ -1,175 +1,166 @@
-use std::collections::HashSet;
-enum MoveInstruction {
-    // These should really be u32, but this will simplify the number of conversions
-    Up(i32),
-    Down(i32),
-    Left(i32),
-    Right(i32),
-}
+use std::collections::{HashMap, HashSet};
 
-#[derive(Debug, Clone)]
-struct Knot {
-    x: i32,
-    y: i32,
-    history: Vec<(i32, i32)>,
+// Moves the head of the rope `distance` steps in `direction`. Directions are
+// single steps, diagonals included, e.g. (1, 1) is up and to the right.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Move {
+    direction: (i32, i32),
+    distance: u32,
 }
 
-impl Knot {
-    fn new() -> Self {
-        Knot {
-            x: 0,
-            y: 0,
-            history: vec![(0, 0)],
-        }
-    }
-    fn is_adjacent(&self, other: &Knot) -> bool {
-        // Check if we're adjacent to a knot including diagonal adjacency
-        let x_diff = (self.x - other.x).abs();
-        let y_diff = (self.y - other.y).abs();
-        if x_diff <= 1 && y_diff <= 1 {
-            return true;
-        }
-        false
-    }
-    // Move
-    // For movement we have to follow the other knots position
-    // First: Check if we are in an adjacent position to the other knot (diagonal included).
-    // If we are we don't need to move
-    // Second: check if we are in the same x or y axis as other knot. If we are not, make
-    // that move first. I.e. if other knot is one row over and 3 up, move diagonal (all at once)
-    // 1 row over and 1 up, then move 1 up.
-    // the knots can't overlap, so the knot will always stop moving when it's one space adjacent to the other
-    // We have to store all the locations (x, y) we've visited in history, not just the last one.
-    fn move_relative(&mut self, other: &Knot) {
-        loop {
-            if self.is_adjacent(other) {
-                self.history.push((self.x, self.y));
-                break;
-            }
-            if self.x != other.x {
-                if self.x < other.x {
-                    self.x += 1;
-                } else {
-                    self.x -= 1;
-                }
-            }
-            if self.y != other.y {
-                if self.y < other.y {
-                    self.y += 1;
-                } else {
-                    self.y -= 1;
-                }
+fn parse_input(input: &str) -> Vec<Move> {
+    // Input is of the following form:
+    // R 4
+    // U 4
+    // L 3
+    // D 1
+    // A direction can also combine two letters for a diagonal move, like `UR 2`
+    input
+        .lines()
+        .map(|line| {
+            let (direction, distance) = line.split_once(' ').unwrap();
+            let direction = direction.chars().fold((0, 0), |(x, y), c| match c {
+                'R' => (x + 1, y),
+                'L' => (x - 1, y),
+                'U' => (x, y + 1),
+                'D' => (x, y - 1),
+                _ => panic!("Invalid direction"),
+            });
+            Move {
+                direction,
+                distance: distance.parse().unwrap(),
             }
-            self.history.push((self.x, self.y));
-        }
-    }
+        })
+        .collect()
 }
 
-#[derive(Debug)]
+// A rope of knots, the first of which is the head. Knots are only
+// remembered where they are now; the places a knot has been are only
+// collected for the knots that are tracked.
+#[derive(Debug, Clone)]
 struct Rope {
-    knots: Vec<Knot>,
+    knots: Vec<(i32, i32)>,
+    visited: HashMap<usize, HashSet<(i32, i32)>>,
 }
 
 impl Rope {
     fn new(length: usize) -> Self {
+        assert!(length > 0, "a rope needs a head");
         Rope {
-            knots: vec![Knot::new(); length],
+            knots: vec![(0, 0); length],
+            visited: HashMap::new(),
         }
     }
-    fn follow_up(&mut self) {
+
+    // Starts remembering where knot `index` goes, counting from the head
+    fn track(mut self, index: usize) -> Self {
+        assert!(index < self.knots.len(), "the rope isn't that long");
+        self.visited
+            .entry(index)
+            .or_default()
+            .insert(self.knots[index]);
+        self
+    }
+
+    fn tail(&self) -> usize {
+        self.knots.len() - 1
+    }
+
+    // Moves the head a single step and lets the rest of the rope catch up.
+    // A knot that's no longer touching the one in front of it moves one step
+    // towards it along both axes, so it ends up next to it again.
+    fn step(&mut self, (dx, dy): (i32, i32)) {
+        self.knots[0].0 += dx;
+        self.knots[0].1 += dy;
         for i in 1..self.knots.len() {
-            // We have to clone here because we can't borrow both as mutable and immutable
-            let temp_knot = self.knots[i - 1].clone();
-            self.knots[i].move_relative(&temp_knot);
+            let (ahead_x, ahead_y) = self.knots[i - 1];
+            let (x, y) = &mut self.knots[i];
+            if (ahead_x - *x).abs() <= 1 && (ahead_y - *y).abs() <= 1 {
+                // The rest of the rope doesn't move either
+                break;
+            }
+            *x += (ahead_x - *x).signum();
+            *y += (ahead_y - *y).signum();
+        }
+        for (index, visited) in self.visited.iter_mut() {
+            visited.insert(self.knots[*index]);
         }
     }
-    fn movement(&mut self, instruction: &MoveInstruction) {
-        // We need to move the rope. We do this by moving the first knot following the instruction
-        // with knot.move_direct(instruction). Then we move the rest of the rope following the first knot
-        // with knot.move_relative(&self.knots[i - 1])
-        match instruction {
-            MoveInstruction::Up(spaces) => {
-                for _ in 0..*spaces {
-                    self.knots[0].y += 1;
-                    self.follow_up();
-                }
-            }
-            MoveInstruction::Down(spaces) => {
-                for _ in 0..*spaces {
-                    self.knots[0].y -= 1;
-                    self.follow_up();
-                }
-            }
-            MoveInstruction::Left(spaces) => {
-                for _ in 0..*spaces {
-                    self.knots[0].x -= 1;
-                    self.follow_up();
-                }
-            }
-            MoveInstruction::Right(spaces) => {
-                for _ in 0..*spaces {
-                    self.knots[0].x += 1;
-                    self.follow_up();
-                }
-            }
+
+    fn apply(&mut self, movement: &Move) {
+        for _ in 0..movement.distance {
+            self.step(movement.direction);
         }
     }
+
+    // The number of places a tracked knot has been
+    fn visited(&self, index: usize) -> Option<usize> {
+        self.visited.get(&index).map(HashSet::len)
+    }
+
+    // Plays the moves one step at a time, yielding where every knot is after each step
+    fn steps<'a>(&'a mut self, moves: &'a [Move]) -> impl Iterator<Item = Vec<(i32, i32)>> + 'a {
+        moves
+            .iter()
+            .flat_map(|movement| (0..movement.distance).map(|_| movement.direction))
+            .map(move |direction| {
+                self.step(direction);
+                self.knots.clone()
+            })
+    }
 }
 
-fn parse_input(input: &str) -> Vec<MoveInstruction> {
-    // Input is of the following form:
-    // R 4
-    // U 4
-    // L 3
-    // D 1
-    input
-        .lines()
-        .map(|line| line.split(' '))
-        .map(|mut line| {
-            let direction = line.next().unwrap();
-            let distance = line.next().unwrap().parse::<i32>().unwrap();
-            match direction {
-                "R" => MoveInstruction::Right(distance),
-                "L" => MoveInstruction::Left(distance),
-                "U" => MoveInstruction::Up(distance),
-                "D" => MoveInstruction::Down(distance),
-                _ => panic!("Invalid direction"),
-            }
-        })
-        .collect()
+// Draws the knots like the puzzle does: `H` for the head, the index for
+// the other knots and `s` for the start. Knots closer to the head are drawn
+// on top.
+fn render(knots: &[(i32, i32)]) -> String {
+    let xs = knots.iter().map(|(x, _)| *x).chain([0]);
+    let ys = knots.iter().map(|(_, y)| *y).chain([0]);
+    let (x_min, x_max) = (xs.clone().min().unwrap(), xs.max().unwrap());
+    let (y_min, y_max) = (ys.clone().min().unwrap(), ys.max().unwrap());
+    let mut rows = vec![];
+    for y in (y_min..=y_max).rev() {
+        let row: String = (x_min..=x_max)
+            .map(|x| match knots.iter().position(|knot| *knot == (x, y)) {
+                Some(0) => 'H',
+                Some(i) => char::from_digit(i as u32, 10).unwrap_or('#'),
+                None if (x, y) == (0, 0) => 's',
+                None => '.',
+            })
+            .collect();
+        rows.push(row);
+    }
+    rows.join("\n")
 }
 
-pub fn part_one(input: &str) -> Option<u32> {
-    let instructions = parse_input(input);
-    let mut rope = Rope::new(2);
-    for (i, instruction) in instructions.iter().enumerate() {
-        rope.movement(instruction);
-        // dbg!(&i + 1, &rope);
+// How many places the tail of a rope of `length` knots visits
+fn tail_visits(input: &str, length: usize) -> Option<u32> {
+    let mut rope = Rope::new(length).track(length - 1);
+    for movement in parse_input(input) {
+        rope.apply(&movement);
     }
-    // Get unique locations in tail.history without using .sort()
-    let tail = &rope.knots[rope.knots.len() - 1];
-    dbg!(&tail.history);
-    let unique_locations: HashSet<(i32, i32)> = tail.history.iter().cloned().collect();
-    Some(dbg!(unique_locations).len() as u32)
+    rope.visited(rope.tail()).map(|count| count as u32)
+}
+
+pub fn part_one(input: &str) -> Option<u32> {
+    tail_visits(input, 2)
 }
 
 pub fn part_two(input: &str) -> Option<u32> {
-    // 2525 = too low
-    let instructions = parse_input(input);
-    let mut rope = Rope::new(10);
-    for (i, instruction) in instructions.iter().enumerate() {
-        rope.movement(instruction);
-        // dbg!(&i + 1, &rope);
-    }
-    // Get unique locations in tail.history without using .sort()
-    let tail = &rope.knots[rope.knots.len() - 1];
-    dbg!(&tail.history);
-    let unique_locations: HashSet<(i32, i32)> = tail.history.iter().cloned().collect();
-    Some((unique_locations).len() as u32)
+    tail_visits(input, 10)
 }
 
 fn main() {
     let input = &advent_of_code::read_file("inputs", 9);
+    // `cargo solve 09 -- --animate` prints the ten knot rope after every step
+    let mut args = pico_args::Arguments::from_env();
+    if args.contains("--animate") {
+        let moves = parse_input(input);
+        let mut rope = Rope::new(10);
+        for knots in rope.steps(&moves) {
+            println!("{}\n", render(&knots));
+        }
+    }
     advent_of_code::solve!(1, part_one, input);
     advent_of_code::solve!(2, part_two, input);
 }
@@ -178,16 +169,35 @@ fn main() {
 mod tests {
     use super::*;
 
+    // The first example from the puzzle. The example file is the larger
+    // second one.
+    const SMALL_EXAMPLE: &str = "R 4\nU 4\nL 3\nD 1\nR 4\nD 1\nL 5\nR 2";
+
     #[test]
     fn test_part_one() {
+        assert_eq!(part_one(SMALL_EXAMPLE), Some(13));
         let input = advent_of_code::read_file("examples", 9);
-        assert_eq!(part_one(&input), Some(13));
+        assert_eq!(part_one(&input), Some(88));
     }
 
     #[test]
-    #[ignore]
     fn test_part_two() {
+        assert_eq!(part_two(SMALL_EXAMPLE), Some(1));
         let input = advent_of_code::read_file("examples", 9);
         assert_eq!(part_two(&input), Some(36));
     }
+
+    #[test]
+    fn test_steps() {
+        let moves = parse_input("UR 2\nL 1");
+        let mut rope = Rope::new(3).track(1).track(2);
+        let steps: Vec<_> = rope.steps(&moves).collect();
+        assert_eq!(steps[0], vec![(1, 1), (0, 0), (0, 0)]);
+        assert_eq!(steps[1], vec![(2, 2), (1, 1), (0, 0)]);
+        assert_eq!(steps[2], vec![(1, 2), (1, 1), (0, 0)]);
+        assert_eq!(render(&steps[1]), "..H\n.1.\n2..");
+        assert_eq!(rope.visited(1), Some(2));
+        assert_eq!(rope.visited(2), Some(1));
+        assert_eq!(rope.visited(0), None);
+    }
 }
@@ -1,149 +1,81 @@
-use std::{collections::VecDeque, str::FromStr};
+use advent_of_code::helpers::cpu::{Cpu, Instruction, InstructionSet, Phase};
+use advent_of_code::helpers::ocr;
 
-#[derive(Debug)]
-struct Register {
-    history: Vec<i32>,
-}
+// The CPU only has a single register, X, which starts at 1
+const X_START: i64 = 1;
+const CRT_WIDTH: usize = 40;
+const CRT_HEIGHT: usize = 6;
 
-impl Register {
-    fn new() -> Self {
-        Self { history: vec![] }
-    }
-}
-#[derive(Debug)]
-enum Instruction {
-    Add(i32),
-    Noop,
+fn instruction_set() -> InstructionSet<i64> {
+    InstructionSet::new()
+        .define("noop", 0, 1, |_, _| {})
+        .define("addx", 1, 2, |x, args| *x += args[0])
 }
 
-fn parse_instruction(input: &str) -> Instruction {
-    let input_split = input.split(' ').collect::<Vec<&str>>();
-    match input_split[..] {
-        [_instr, amount] => {
-            let value = i32::from_str(amount).unwrap();
-            Instruction::Add(value)
-        }
-        ["noop"] => Instruction::Noop,
-        _ => panic!("Invalid instruction"),
-    }
+fn parse_input(set: &InstructionSet<i64>, input: &str) -> Vec<Instruction> {
+    set.parse_program(input).unwrap()
 }
 
-#[derive(Debug)]
-struct Operation {
-    value: i32,
-    cycles: u32,
-}
-
-impl Operation {
-    fn new(value: i32, cycles: u32) -> Self {
-        Self { value, cycles }
-    }
-    fn complete(&self) -> bool {
-        self.cycles == 0
-    }
-}
-impl From<&Instruction> for Operation {
-    fn from(instruction: &Instruction) -> Self {
-        match instruction {
-            Instruction::Add(value) => Self::new(*value, 2),
-            Instruction::Noop => Self::new(0, 1),
-        }
-    }
-}
-
-pub fn part_one(input: &str) -> Option<i32> {
-    let instructions = input
-        .lines()
-        .map(parse_instruction)
-        .collect::<Vec<Instruction>>();
-    let mut operations_queue: VecDeque<Operation> = VecDeque::new();
-    let mut register = Register::new();
-    let mut cycle = 1;
-    while !instructions.is_empty() || !operations_queue.is_empty() {
-        let instruction = instructions.get(cycle - 1);
-        if let Some(instruction) = instruction {
-            operations_queue.push_back(instruction.into());
-        }
-        if operations_queue.is_empty() {
-            break;
-        }
-        let mut operation = operations_queue.pop_front().unwrap();
-        operation.cycles -= 1;
-
-        if operation.complete() {
-            let value = operation.value;
-            let history = register.history.last().unwrap_or(&1);
-            register.history.push(*history + value);
-        } else {
-            operations_queue.push_front(operation);
-            let history = register.history.last().unwrap_or(&1);
-            register.history.push(*history);
-        }
-        cycle += 1;
-    }
+pub fn part_one(input: &str) -> Option<i64> {
+    let set = instruction_set();
+    let program = parse_input(&set, input);
+    // The signal strength is measured *during* the 20th cycle and every 40
+    // cycles after that, so the instruction finishing in that cycle hasn't
+    // changed X yet
     let mut sum = 0;
-    for cycle in (1..register.history.len()).skip(19).step_by(40) {
-        let reg_value = register.history.get(cycle - 2).unwrap();
-        sum += reg_value * cycle as i32;
-    }
-
+    Cpu::new(&set, X_START)
+        .run(&program, |tick| {
+            if tick.phase == Phase::During && tick.cycle % 40 == 20 {
+                sum += tick.cycle as i64 * tick.registers;
+            }
+        })
+        .ok()?;
     Some(sum)
 }
 
-pub fn part_two(input: &str) -> String {
-    let instructions = input
-        .lines()
-        .map(parse_instruction)
-        .collect::<Vec<Instruction>>();
-    let mut operations_queue: VecDeque<Operation> = VecDeque::new();
-    let mut register = Register::new();
-    let mut cycle = 1;
-    while !instructions.is_empty() || !operations_queue.is_empty() {
-        let instruction = instructions.get(cycle - 1);
-        if let Some(instruction) = instruction {
-            operations_queue.push_back(instruction.into());
-        }
-        if operations_queue.is_empty() {
-            break;
-        }
-        let mut operation = operations_queue.pop_front().unwrap();
-        operation.cycles -= 1;
-        if operation.complete() {
-            let value = operation.value;
-            let history = register.history.last().unwrap_or(&1);
-            register.history.push(*history + value);
-        } else {
-            operations_queue.push_front(operation);
-            let history = register.history.last().unwrap_or(&1);
-            register.history.push(*history);
-        }
-        cycle += 1;
-    }
-    let output: String = "".to_string();
-    // So if we were better, we would have come up with an abstraction
-    // That does these things in order (sprite draw, then register value)
-    // But we didn't, so we're just going to add a 1 to our history vector
-    let mut history_copy = register.history.clone();
-    let mut new_history = vec![1];
-    new_history.append(&mut history_copy);
-    for (i, x) in new_history.iter().enumerate() {
-        let sprite_range = x - 1..=x + 1;
-        let horizontal_position = i % 40;
-        match sprite_range.contains(&(horizontal_position as i32)) {
-            true => print!("#"),
-            false => print!("."),
-        }
-        if horizontal_position == 39 {
-            println!();
-        }
-    }
-    output
+// What the CRT shows once the program has run, one row per line
+fn render(input: &str) -> Option<String> {
+    let set = instruction_set();
+    let program = parse_input(&set, input);
+    // X is the horizontal position of the middle of a 3 pixel wide sprite.
+    // During each cycle the CRT draws one pixel, which is lit if the sprite
+    // covers it.
+    let mut pixels = vec![vec!['.'; CRT_WIDTH]; CRT_HEIGHT];
+    Cpu::new(&set, X_START)
+        .run(&program, |tick| {
+            let index = (tick.cycle - 1) as usize;
+            if tick.phase != Phase::During || index >= CRT_WIDTH * CRT_HEIGHT {
+                return;
+            }
+            let (row, column) = (index / CRT_WIDTH, index % CRT_WIDTH);
+            if (tick.registers - column as i64).abs() <= 1 {
+                pixels[row][column] = '#';
+            }
+        })
+        .ok()?;
+    let rows: Vec<String> = pixels.iter().map(|row| row.iter().collect()).collect();
+    Some(rows.join("\n"))
+}
+
+pub fn part_two(input: &str) -> Option<String> {
+    ocr::recognize(&render(input)?).ok()
 }
 
 fn main() {
     let input = &advent_of_code::read_file("inputs", 10);
+    // `cargo solve 10 -- --trace trace.tsv` writes what happens in every cycle to a file
+    let mut args = pico_args::Arguments::from_env();
+    let trace: Option<String> = args.opt_value_from_str("--trace").unwrap();
+    if let Some(path) = trace {
+        let set = instruction_set();
+        let program = parse_input(&set, input);
+        Cpu::new(&set, X_START)
+            .trace_to_file(&path)
+            .and_then(|mut cpu| cpu.run(&program, |_| {}))
+            .unwrap();
+    }
     advent_of_code::solve!(1, part_one, input);
-    part_two(input);
+    advent_of_code::solve!(2, part_two, input);
 }
 
 #[cfg(test)]
@@ -156,9 +88,29 @@ mod tests {
         assert_eq!(part_one(&input), Some(13140));
     }
 
+    #[test]
+    fn test_render() {
+        let input = advent_of_code::read_file("examples", 10);
+        assert_eq!(
+            render(&input),
+            Some(
+                [
+                    "##..##..##..##..##..##..##..##..##..##..",
+                    "###...###...###...###...###...###...###.",
+                    "####....####....####....####....####....",
+                    "#####.....#####.....#####.....#####.....",
+                    "######......######......######......####",
+                    "#######.......#######.......#######.....",
+                ]
+                .join("\n")
+            )
+        );
+    }
+
     #[test]
     fn test_part_two() {
+        // The example draws a test pattern rather than letters
         let input = advent_of_code::read_file("examples", 10);
-        part_two(&input);
+        assert_eq!(part_two(&input), None);
     }
 }
@@ -1,206 +1,365 @@
+use advent_of_code::helpers::parse;
 use num::integer::lcm;
-use regex::Regex;
-use std::cell::RefCell;
-use std::collections::{BTreeMap, VecDeque};
+use std::collections::VecDeque;
+use std::fmt;
+use std::str::FromStr;
 
-#[derive(Debug)]
+// An arithmetic expression over the old worry level, e.g. `old * old + 3`.
+// `*` binds tighter than `+`, and parentheses work as you'd expect.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Expr {
+    Old,
+    Number(u64),
+    Add(Box<Expr>, Box<Expr>),
+    Multiply(Box<Expr>, Box<Expr>),
+}
 
-enum Operation {
-    Add(u64),
-    Multiply(u64),
-    Square,
+impl Expr {
+    // With a modulus every intermediate result is reduced, so nothing overflows
+    // as long as the modulus squared fits in a u64
+    fn eval(&self, old: u64, modulus: Option<u64>) -> u64 {
+        let value = match self {
+            Expr::Old => old,
+            Expr::Number(n) => *n,
+            Expr::Add(a, b) => a.eval(old, modulus) + b.eval(old, modulus),
+            Expr::Multiply(a, b) => a.eval(old, modulus) * b.eval(old, modulus),
+        };
+        modulus.map_or(value, |m| value % m)
+    }
 }
 
-#[derive(Debug)]
-struct Monkey {
-    id: u64,
-    starting_items: VecDeque<u64>,
-    // Add or Multiply by a number
-    operation: Operation,
-    divisibility_test: u64,
-    // if true: send to monkey id, if false send to other monkey id
-    true_branch: u64,
-    false_branch: u64,
-    // need this for the puzzle
-    inspection_count: u64,
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Old => write!(f, "old"),
+            Expr::Number(n) => write!(f, "{}", n),
+            Expr::Add(a, b) => write!(f, "({} + {})", a, b),
+            Expr::Multiply(a, b) => write!(f, "({} * {})", a, b),
+        }
+    }
 }
 
-impl Monkey {
-    fn from_hashmap(dict: BTreeMap<String, String>) -> Self {
-        let id: u64 = dict["monkey_id"].parse().unwrap();
-        let starting_items: VecDeque<u64> = dict["starting_items"]
-            .split(", ")
-            .map(|s| s.parse().unwrap())
-            .collect();
-        // example operation: new = old * 19
-        // we need the operation and the digit
-        let operation_string = dict["operation"].split(' ').rev().collect::<Vec<&str>>();
-        let operation = match operation_string[0..=1] {
-            ["old", "*"] => Operation::Square,
-            [digit, "+"] => Operation::Add(digit.parse().unwrap()),
-            [digit, "*"] => Operation::Multiply(digit.parse().unwrap()),
-            _ => panic!("Invalid operation"),
-        };
-        // capture = 'divisible by 23'
-        let divisibility_test: u64 = dict["test"].split(' ').last().unwrap().parse().unwrap();
-        // capture = 'throw to monkey 2'
-        let true_branch: u64 = dict["true"].split(' ').last().unwrap().parse().unwrap();
-        let false_branch: u64 = dict["false"].split(' ').last().unwrap().parse().unwrap();
+// A recursive descent parser. Tokens are single symbols or runs of anything
+// else, so `old*2` and `old * 2` read the same.
+struct ExprParser<'a> {
+    tokens: Vec<&'a str>,
+    position: usize,
+}
+
+impl<'a> ExprParser<'a> {
+    fn new(input: &'a str) -> Self {
+        let mut tokens = vec![];
+        let mut start = None;
+        for (i, c) in input.char_indices() {
+            if c.is_alphanumeric() {
+                start.get_or_insert(i);
+                continue;
+            }
+            if let Some(start) = start.take() {
+                tokens.push(&input[start..i]);
+            }
+            if !c.is_whitespace() {
+                tokens.push(&input[i..i + c.len_utf8()]);
+            }
+        }
+        if let Some(start) = start {
+            tokens.push(&input[start..]);
+        }
         Self {
-            id,
-            starting_items,
-            operation,
-            divisibility_test,
-            true_branch,
-            false_branch,
-            inspection_count: 0,
+            tokens,
+            position: 0,
         }
     }
-    fn calculate_worry(&self, item: u64) -> u64 {
-        let post_inspection_value = match &self.operation {
-            Operation::Add(value) => item + value,
-            Operation::Multiply(value) => item * value,
-            Operation::Square => item * item,
-        };
-        // divide post_inspection_value by 3 and round down to the nearest integer
-        let relief_value = (post_inspection_value as f32 / 3.0).floor() as u64;
-        relief_value
-    }
-    fn calculate_worry_no_relief(&self, item: u64, modulo: u64) -> u64 {
-        let post_inspection_value = match &self.operation {
-            Operation::Add(value) => (item + value) % modulo,
-            Operation::Multiply(value) => (item * value) % modulo,
-            Operation::Square => (item * item) % modulo,
-        };
-        post_inspection_value
+
+    fn peek(&self) -> Option<&'a str> {
+        self.tokens.get(self.position).copied()
     }
 
-    // Returns the next monkey id and the item to send to that monkey
-    fn inspect_item(&mut self, item: u64) -> (u64, u64) {
-        self.inspection_count += 1;
-        let new_item = self.calculate_worry(item);
-        if new_item % self.divisibility_test == 0 {
-            (self.true_branch, new_item)
-        } else {
-            (self.false_branch, new_item)
+    fn next(&mut self) -> Result<&'a str, String> {
+        let token = self.peek().ok_or("Unexpected end of expression")?;
+        self.position += 1;
+        Ok(token)
+    }
+
+    // sum = product ('+' product)*
+    fn sum(&mut self) -> Result<Expr, String> {
+        let mut expr = self.product()?;
+        while self.peek() == Some("+") {
+            self.position += 1;
+            expr = Expr::Add(Box::new(expr), Box::new(self.product()?));
         }
+        Ok(expr)
     }
 
-    fn inspect_item_no_relief(&mut self, item: u64, modulo: u64) -> (u64, u64) {
-        self.inspection_count += 1;
-        let new_item = self.calculate_worry_no_relief(item, modulo);
-        if new_item % self.divisibility_test == 0 {
-            (self.true_branch, new_item)
-        } else {
-            (self.false_branch, new_item)
+    // product = atom ('*' atom)*
+    fn product(&mut self) -> Result<Expr, String> {
+        let mut expr = self.atom()?;
+        while self.peek() == Some("*") {
+            self.position += 1;
+            expr = Expr::Multiply(Box::new(expr), Box::new(self.atom()?));
+        }
+        Ok(expr)
+    }
+
+    // atom = 'old' | number | '(' sum ')'
+    fn atom(&mut self) -> Result<Expr, String> {
+        match self.next()? {
+            "old" => Ok(Expr::Old),
+            "(" => {
+                let expr = self.sum()?;
+                match self.next()? {
+                    ")" => Ok(expr),
+                    token => Err(format!("Expected ')', got '{}'", token)),
+                }
+            }
+            token => token
+                .parse()
+                .map(Expr::Number)
+                .map_err(|_| format!("Unexpected token '{}'", token)),
         }
     }
 }
 
-fn named_captures_to_hashmap(
-    re: regex::Regex,
-    captures: regex::Captures,
-) -> BTreeMap<String, String> {
-    let dict: BTreeMap<String, String> = re
-        .capture_names()
-        .flatten()
-        .filter_map(|n| Some((n.to_string(), captures.name(n)?.as_str().to_string())))
-        .collect();
-    dict
+impl FromStr for Expr {
+    type Err = String;
+
+    // Accepts either a bare expression or the puzzle's `new = <expression>`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let s = s.strip_prefix("new =").unwrap_or(s);
+        let mut parser = ExprParser::new(s);
+        let expr = parser.sum()?;
+        match parser.peek() {
+            None => Ok(expr),
+            Some(token) => Err(format!("Unexpected token '{}'", token)),
+        }
+    }
 }
 
-fn parse_monkey_data(input: &str) -> BTreeMap<String, String> {
-    // In retrospect, a simpler parser would be to split each line on a colon and
-    // then take the last element as the string
-    // but I guess we get some additional validation here in the case where our regex fails
-    let re = Regex::new(r"Monkey (?P<monkey_id>\d):\n  Starting items: (?P<starting_items>[\d, ]+)\n  Operation: (?P<operation>.*)\n  Test: (?P<test>.*)\n    If true: (?P<true>.*)\n    If false: (?P<false>.*)").unwrap();
-    let captures = re.captures(input).unwrap();
-    // https://stackoverflow.com/a/54259908
-    let dict = named_captures_to_hashmap(re, captures);
-    dict
+#[derive(Debug, Clone)]
+struct Monkey {
+    items: VecDeque<u64>,
+    operation: Expr,
+    divisibility_test: u64,
+    // if the test passes send the item to this monkey, otherwise to the other one
+    true_branch: usize,
+    false_branch: usize,
 }
 
-pub fn part_one(input: &str) -> Option<u64> {
-    let rounds: u64 = 20;
-    let mut monkeys: BTreeMap<u64, RefCell<Monkey>> = BTreeMap::new();
-    for monkey_string in input.split("\n\n") {
-        let monkey_data = parse_monkey_data(monkey_string);
-        let monkey = Monkey::from_hashmap(monkey_data);
-        monkeys.insert(monkey.id, RefCell::new(monkey));
-    }
-    for round in 0..rounds {
-        for monkey in monkeys.values() {
-            let mut monkey = monkey.borrow_mut();
-            for _ in 0..monkey.starting_items.len() {
-                let item = monkey.starting_items.pop_front().unwrap();
-                let (next_monkey_id, new_item) = monkey.inspect_item(item);
-                let next_monkey = monkeys.get(&next_monkey_id).unwrap();
-                let mut next_monkey = next_monkey.borrow_mut();
-                next_monkey.starting_items.push_back(new_item);
+impl Monkey {
+    fn throw_to(&self, item: u64) -> usize {
+        if item.is_multiple_of(self.divisibility_test) {
+            self.true_branch
+        } else {
+            self.false_branch
+        }
+    }
+}
+
+// The last word of lines like `divisible by 23` or `throw to monkey 2`
+fn last_number<T: FromStr>(value: &str) -> Result<T, String> {
+    value
+        .split(' ')
+        .next_back()
+        .and_then(|word| word.parse().ok())
+        .ok_or_else(|| format!("Expected a number at the end of '{}'", value))
+}
+
+impl FromStr for Monkey {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut items = None;
+        let mut operation = None;
+        let mut divisibility_test = None;
+        let mut true_branch = None;
+        let mut false_branch = None;
+        // Every line of a monkey is a `key: value` record, including the
+        // `Monkey 0:` header. The header is ignored as monkeys are listed in order.
+        for (key, value) in parse::records(s).map_err(|e| e.to_string())? {
+            match key {
+                _ if key.starts_with("Monkey ") => {}
+                "Starting items" => {
+                    items = Some(
+                        value
+                            .split(", ")
+                            .map(|item| item.parse().map_err(|_| format!("Bad item: {}", item)))
+                            .collect::<Result<VecDeque<u64>, String>>()?,
+                    )
+                }
+                "Operation" => operation = Some(value.parse()?),
+                "Test" => divisibility_test = Some(last_number(value)?),
+                "If true" => true_branch = Some(last_number(value)?),
+                "If false" => false_branch = Some(last_number(value)?),
+                _ => return Err(format!("Unknown monkey attribute: {}", key)),
             }
         }
+        let missing = |name: &str| format!("Monkey is missing '{}'", name);
+        Ok(Monkey {
+            items: items.ok_or_else(|| missing("Starting items"))?,
+            operation: operation.ok_or_else(|| missing("Operation"))?,
+            divisibility_test: divisibility_test.ok_or_else(|| missing("Test"))?,
+            true_branch: true_branch.ok_or_else(|| missing("If true"))?,
+            false_branch: false_branch.ok_or_else(|| missing("If false"))?,
+        })
     }
-    // println!("{:#?}", monkeys);
-    // build a vector of the monkeys and their inspection counts, sorted greatest to least
-    let mut monkey_inspection_counts: Vec<(u64, u64)> = monkeys
-        .values()
-        .map(|m| {
-            let m = m.borrow();
-            (m.id, m.inspection_count)
+}
+
+// What happens to a worry level after an inspection
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Worry {
+    // Divided by 3, because the item wasn't damaged
+    Relief,
+    // Left as it is, which overflows soon enough
+    Unchanged,
+    // Kept modulo this number
+    Modular(u64),
+}
+
+// A group of monkeys throwing items around. By default the worry level is
+// divided by 3 after every inspection, like in part one.
+#[derive(Debug, Clone)]
+struct Troop {
+    monkeys: Vec<Monkey>,
+    worry: Worry,
+    // How many items each monkey inspected in every round played so far
+    rounds: Vec<Vec<u64>>,
+}
+
+impl FromStr for Troop {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let monkeys = parse::blocks(s)
+            .into_iter()
+            .map(str::parse)
+            .collect::<Result<Vec<Monkey>, String>>()?;
+        for monkey in &monkeys {
+            if monkey.true_branch.max(monkey.false_branch) >= monkeys.len() {
+                return Err("Monkey throws to a monkey that doesn't exist".to_string());
+            }
+        }
+        Ok(Troop {
+            monkeys,
+            worry: Worry::Relief,
+            rounds: vec![],
         })
-        .collect();
-    monkey_inspection_counts.sort_by(|a, b| b.1.cmp(&a.1));
-    // multiply the inspection counts of the top 2 monkeys
-    let top_two = &monkey_inspection_counts[0..2];
-    let answer = top_two.iter().fold(1, |acc, x| acc * x.1);
-    Some(answer)
+    }
 }
 
-pub fn part_two(input: &str) -> Option<u64> {
-    let rounds: u64 = 10_000;
-    let mut monkeys: BTreeMap<u64, RefCell<Monkey>> = BTreeMap::new();
-    let mut divisors: Vec<u64> = Vec::new();
-    for monkey_string in input.split("\n\n") {
-        let monkey_data = parse_monkey_data(monkey_string);
-        let monkey = Monkey::from_hashmap(monkey_data);
-        divisors.push(monkey.divisibility_test.to_owned());
-        monkeys.insert(monkey.id, RefCell::new(monkey));
-    }
-    // collect all the divisibility rules from monkeys
-    let modulo = divisors.iter().product();
-    for round in 0..rounds {
-        for monkey in monkeys.values() {
-            let mut monkey = monkey.borrow_mut();
-            for _ in 0..monkey.starting_items.len() {
-                let item = monkey.starting_items.pop_front().unwrap();
-                let (next_monkey_id, new_item) = monkey.inspect_item_no_relief(item, modulo);
-                let next_monkey = monkeys.get(&next_monkey_id).unwrap();
-                let mut next_monkey = next_monkey.borrow_mut();
-                next_monkey.starting_items.push_back(new_item);
+impl Troop {
+    // Every monkey only cares about whether a worry level is divisible by its
+    // own number, so with `reduce` we keep the levels modulo the lcm of all of
+    // them. Dividing by 3 doesn't survive that, so it can't go with `relief`.
+    fn configure(mut self, relief: bool, reduce: bool) -> Result<Self, String> {
+        self.worry = match (relief, reduce) {
+            (true, true) => {
+                return Err("Relief can't be combined with modular reduction".to_string())
+            }
+            (true, false) => Worry::Relief,
+            (false, false) => Worry::Unchanged,
+            (false, true) => Worry::Modular(
+                self.monkeys
+                    .iter()
+                    .map(|monkey| monkey.divisibility_test)
+                    .fold(1, lcm),
+            ),
+        };
+        Ok(self)
+    }
+
+    // Plays one round and returns how many items each monkey inspected
+    fn round(&mut self) -> Vec<u64> {
+        let mut inspections = vec![0; self.monkeys.len()];
+        for (id, count) in inspections.iter_mut().enumerate() {
+            while let Some(item) = self.monkeys[id].items.pop_front() {
+                *count += 1;
+                let monkey = &self.monkeys[id];
+                let item = match self.worry {
+                    Worry::Relief => monkey.operation.eval(item, None) / 3,
+                    Worry::Unchanged => monkey.operation.eval(item, None),
+                    Worry::Modular(modulus) => monkey.operation.eval(item, Some(modulus)),
+                };
+                let next = monkey.throw_to(item);
+                self.monkeys[next].items.push_back(item);
             }
         }
+        self.rounds.push(inspections.clone());
+        inspections
     }
-    // println!("{:#?}", monkeys);
-    // build a vector of the monkeys and their inspection counts, sorted greatest to least
-    let mut monkey_inspection_counts: Vec<(u64, u64)> = monkeys
-        .values()
-        .map(|m| {
-            let m = m.borrow();
-            (m.id, m.inspection_count)
-        })
-        .collect();
-    monkey_inspection_counts.sort_by(|a, b| b.1.cmp(&a.1));
-    // multiply the inspection counts of the top 2 monkeys
-    let top_two = &monkey_inspection_counts[0..2];
-    let answer = top_two.iter().fold(1, |acc, x| acc * x.1);
-    Some(answer)
-    // dbg!(monkeys);
-    // dbg!(&top_two);
-    // None
+
+    fn play(&mut self, rounds: usize) {
+        for _ in 0..rounds {
+            self.round();
+        }
+    }
+
+    // The number of items each monkey inspected over all the rounds so far
+    fn inspections(&self) -> Vec<u64> {
+        let mut totals = vec![0; self.monkeys.len()];
+        for round in &self.rounds {
+            for (total, count) in totals.iter_mut().zip(round) {
+                *total += count;
+            }
+        }
+        totals
+    }
+
+    // The inspection counts of the two most active monkeys multiplied together
+    fn monkey_business(&self) -> u64 {
+        let mut inspections = self.inspections();
+        inspections.sort_unstable_by(|a, b| b.cmp(a));
+        inspections.iter().take(2).product()
+    }
+
+    // The inspection totals after the given rounds, as the puzzle text shows them
+    fn report(&self, after: &[usize]) -> String {
+        let mut report = String::new();
+        let mut totals = vec![0; self.monkeys.len()];
+        for (i, round) in self.rounds.iter().enumerate() {
+            for (total, count) in totals.iter_mut().zip(round) {
+                *total += count;
+            }
+            if after.contains(&(i + 1)) {
+                report += &format!("== After round {} ==\n", i + 1);
+                for (id, total) in totals.iter().enumerate() {
+                    report += &format!("Monkey {} inspected items {} times.\n", id, total);
+                }
+                report += "\n";
+            }
+        }
+        report
+    }
+}
+
+pub fn part_one(input: &str) -> Option<u64> {
+    let mut troop: Troop = input.parse().ok()?;
+    troop.play(20);
+    Some(troop.monkey_business())
+}
+
+pub fn part_two(input: &str) -> Option<u64> {
+    let mut troop = input.parse::<Troop>().ok()?.configure(false, true).ok()?;
+    troop.play(10_000);
+    Some(troop.monkey_business())
 }
 
 fn main() {
     let input = &advent_of_code::read_file("inputs", 11);
+    // `cargo solve 11 -- --report` prints how busy each monkey was during part two
+    let mut args = pico_args::Arguments::from_env();
+    if args.contains("--report") {
+        let mut troop = input
+            .parse::<Troop>()
+            .and_then(|troop| troop.configure(false, true))
+            .unwrap();
+        troop.play(10_000);
+        let after: Vec<usize> = [1, 20]
+            .into_iter()
+            .chain((1..=10).map(|i| i * 1000))
+            .collect();
+        print!("{}", troop.report(&after));
+    }
     advent_of_code::solve!(1, part_one, input);
     advent_of_code::solve!(2, part_two, input);
 }
@@ -220,4 +379,44 @@ mod tests {
         let input = advent_of_code::read_file("examples", 11);
         assert_eq!(part_two(&input), Some(2713310158));
     }
+
+    #[test]
+    fn test_reduction_needs_no_relief() {
+        let input = advent_of_code::read_file("examples", 11);
+        let troop = input.parse::<Troop>().unwrap();
+        assert!(troop.clone().configure(true, true).is_err());
+        assert_eq!(
+            troop.configure(false, false).unwrap().worry,
+            Worry::Unchanged
+        );
+    }
+
+    #[test]
+    fn test_expressions() {
+        let expr: Expr = "new = old * old + 3".parse().unwrap();
+        assert_eq!(expr.to_string(), "((old * old) + 3)");
+        assert_eq!(expr.eval(4, None), 19);
+        assert_eq!(expr.eval(4, Some(5)), 4);
+        let expr: Expr = "2 * (old + 1)".parse().unwrap();
+        assert_eq!(expr.eval(4, None), 10);
+        assert!("old +".parse::<Expr>().is_err());
+        assert!("old - 1".parse::<Expr>().is_err());
+        assert!("(old * 2".parse::<Expr>().is_err());
+    }
+
+    #[test]
+    fn test_round_statistics() {
+        let input = advent_of_code::read_file("examples", 11);
+        let mut troop = input
+            .parse::<Troop>()
+            .unwrap()
+            .configure(false, true)
+            .unwrap();
+        assert_eq!(troop.round(), vec![2, 4, 3, 6]);
+        troop.play(19);
+        assert_eq!(troop.inspections(), vec![99, 97, 8, 103]);
+        assert!(troop
+            .report(&[20])
+            .starts_with("== After round 20 ==\nMonkey 0 inspected items 99 times.\n"));
+    }
 }
@@ -1,6 +1,8 @@
 use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
 
-use serde_json::Value;
+use advent_of_code::helpers::parse::{self, ParseError};
 
 #[derive(Debug, Clone, Eq, PartialEq)]
 enum Packet {
@@ -8,19 +10,109 @@ enum Packet {
     Value(u32),
 }
 
-impl TryFrom<Value> for Packet {
-    type Error = String;
-    fn try_from(value: Value) -> Result<Self, Self::Error> {
-        match value {
-            Value::Array(arr) => {
-                let mut packets = Vec::new();
-                for item in arr {
-                    packets.push(Packet::try_from(item)?);
+// A recursive descent parser for the packet grammar:
+//   packet = list | integer
+//   list   = '[' (packet (',' packet)*)? ']'
+// Packets are a single line, so errors only need the column.
+struct PacketParser<'a> {
+    input: &'a str,
+    position: usize,
+}
+
+impl<'a> PacketParser<'a> {
+    fn error(&self, message: impl Into<String>) -> ParseError {
+        ParseError::new(1, self.position + 1, message)
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.input.as_bytes().get(self.position).copied()
+    }
+
+    fn expect(&mut self, expected: u8) -> Result<(), ParseError> {
+        match self.peek() {
+            Some(c) if c == expected => {
+                self.position += 1;
+                Ok(())
+            }
+            Some(c) => Err(self.error(format!(
+                "expected '{}', found '{}'",
+                expected as char, c as char
+            ))),
+            None => Err(self.error(format!("expected '{}', found end", expected as char))),
+        }
+    }
+
+    fn packet(&mut self) -> Result<Packet, ParseError> {
+        match self.peek() {
+            Some(b'[') => self.list(),
+            Some(c) if c.is_ascii_digit() => self.integer(),
+            Some(c) => Err(self.error(format!("unexpected '{}'", c as char))),
+            None => Err(self.error("unexpected end of packet")),
+        }
+    }
+
+    fn list(&mut self) -> Result<Packet, ParseError> {
+        self.expect(b'[')?;
+        let mut packets = vec![];
+        if self.peek() == Some(b']') {
+            self.position += 1;
+            return Ok(Packet::Collection(packets));
+        }
+        loop {
+            packets.push(self.packet()?);
+            match self.peek() {
+                Some(b',') => self.position += 1,
+                _ => break,
+            }
+        }
+        self.expect(b']')?;
+        Ok(Packet::Collection(packets))
+    }
+
+    fn integer(&mut self) -> Result<Packet, ParseError> {
+        let start = self.position;
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.position += 1;
+        }
+        let digits = &self.input[start..self.position];
+        digits
+            .parse()
+            .map(Packet::Value)
+            .map_err(|e| ParseError::new(1, start + 1, format!("{}: {}", digits, e)))
+    }
+}
+
+impl FromStr for Packet {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = PacketParser {
+            input: s,
+            position: 0,
+        };
+        let packet = parser.packet()?;
+        if parser.position < s.len() {
+            return Err(parser.error("trailing characters after packet"));
+        }
+        Ok(packet)
+    }
+}
+
+// Writes packets exactly the way they appear in the input
+impl fmt::Display for Packet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Packet::Value(value) => write!(f, "{}", value),
+            Packet::Collection(packets) => {
+                write!(f, "[")?;
+                for (i, packet) in packets.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", packet)?;
                 }
-                Ok(Packet::Collection(packets))
+                write!(f, "]")
             }
-            Value::Number(num) => Ok(Packet::Value(num.as_u64().unwrap() as u32)),
-            _ => Err("Invalid packet".to_string()),
         }
     }
 }
@@ -30,10 +122,14 @@ impl Ord for Packet {
         match (self, other) {
             (Packet::Value(a), Packet::Value(b)) => a.cmp(b),
             (Packet::Collection(a), Packet::Collection(b)) => a.cmp(b),
-            (Packet::Value(a), Packet::Collection(b)) => {
-                Packet::Collection(vec![Packet::Value(*a)]).cmp(&Packet::Collection(b.clone()))
+            // A value compared to a list acts like a list holding just that
+            // value, which is what a one element slice borrowing it is
+            (Packet::Value(_), Packet::Collection(b)) => {
+                std::slice::from_ref(self).cmp(b.as_slice())
+            }
+            (Packet::Collection(a), Packet::Value(_)) => {
+                a.as_slice().cmp(std::slice::from_ref(other))
             }
-            (Packet::Collection(a), Packet::Value(b)) => a.cmp(&vec![Packet::Value(*b)]),
         }
     }
 }
@@ -44,52 +140,43 @@ impl PartialOrd for Packet {
     }
 }
 
+// Parses one packet per line, reporting errors with the line they're on
+fn parse_packets(input: &str) -> Result<Vec<Packet>, ParseError> {
+    input
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.is_empty())
+        .map(|(i, line)| {
+            line.parse()
+                .map_err(|e: ParseError| ParseError::new(i + 1, e.column, e.message))
+        })
+        .collect()
+}
+
 pub fn part_one(input: &str) -> Option<u32> {
-    let mut ordered_pair_indexes: Vec<u32> = Vec::new();
-    for (i, pair) in input.split("\n\n").enumerate() {
-        let packet_strs: Vec<&str> = pair.lines().collect();
-        let p1_json: Value = serde_json::from_str(packet_strs[0]).unwrap();
-        let p2_json: Value = serde_json::from_str(packet_strs[1]).unwrap();
-        let p1 = Packet::try_from(p1_json).unwrap();
-        let p2 = Packet::try_from(p2_json).unwrap();
-        if p1.cmp(&p2) == Ordering::Less {
-            ordered_pair_indexes.push((i + 1) as u32);
+    let mut sum = 0;
+    for (i, pair) in parse::blocks(input).into_iter().enumerate() {
+        match &parse_packets(pair).ok()?[..] {
+            [left, right] if left < right => sum += i as u32 + 1,
+            [_, _] => {}
+            _ => return None,
         }
     }
-    Some(ordered_pair_indexes.iter().sum())
+    Some(sum)
 }
 
 pub fn part_two(input: &str) -> Option<u32> {
-    let mut all_packets: Vec<Packet> = Vec::new();
-    for (i, input_str) in input.split("\n").filter(|l| !l.is_empty()).enumerate() {
-        let p1_json: Value = serde_json::from_str(input_str).unwrap();
-        let p1 = Packet::try_from(p1_json).unwrap();
-        all_packets.push(p1);
-    }
-    let divider_packets = vec!["[[2]]", "[[6]]"]
+    let mut all_packets = parse_packets(input).ok()?;
+    let divider_packets: Vec<Packet> = ["[[2]]", "[[6]]"]
         .iter()
-        .map(|s| serde_json::from_str(s).unwrap())
-        .map(|v: Value| Packet::try_from(v).unwrap())
-        .collect::<Vec<Packet>>();
-    for packet in divider_packets.iter() {
-        all_packets.push(packet.clone());
-    }
+        .map(|s| s.parse().unwrap())
+        .collect();
+    all_packets.extend(divider_packets.iter().cloned());
     all_packets.sort();
-    let divider_packet_one_index = all_packets
-        .iter()
-        .enumerate()
-        .find(|(_, p)| p == &&divider_packets[0])
-        .unwrap()
-        .0
-        + 1;
-    let divider_packet_two_index = all_packets
-        .iter()
-        .enumerate()
-        .find(|(_, p)| p == &&divider_packets[1])
-        .unwrap()
-        .0
-        + 1;
-    Some((divider_packet_one_index * divider_packet_two_index) as u32)
+    let position = |divider: &Packet| all_packets.binary_search(divider).ok().map(|i| i + 1);
+    let first = position(&divider_packets[0])?;
+    let second = position(&divider_packets[1])?;
+    Some((first * second) as u32)
 }
 
 fn main() {
@@ -113,4 +200,28 @@ mod tests {
         let input = advent_of_code::read_file("examples", 13);
         assert_eq!(part_two(&input), Some(140));
     }
+
+    #[test]
+    fn test_round_trip() {
+        let input = advent_of_code::read_file("examples", 13);
+        for line in input.lines().filter(|line| !line.is_empty()) {
+            assert_eq!(line.parse::<Packet>().unwrap().to_string(), line);
+        }
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = |s: &str| s.parse::<Packet>().unwrap_err().to_string();
+        assert_eq!(error("[1,2"), "1:5: expected ']', found end");
+        assert_eq!(error("[1,,2]"), "1:4: unexpected ','");
+        assert_eq!(error("[1]]"), "1:4: trailing characters after packet");
+        assert_eq!(
+            error("[99999999999]"),
+            "1:2: 99999999999: number too large to fit in target type"
+        );
+        assert_eq!(
+            parse_packets("[1]\n\n[[a]]").unwrap_err().to_string(),
+            "3:3: unexpected 'a'"
+        );
+    }
 }
@@ -1,18 +1,18 @@
-use std::{cmp::Ordering, collections::HashSet};
+use std::cmp::Ordering;
 
-struct Cave {
-    objects: HashSet<(i64, u64)>,
-    height: u64,
-}
+use advent_of_code::helpers::particles::{Engine, Outcome};
+
+// Sand pours into the cave from here
+const SOURCE: (i64, i64) = (500, 0);
 
-fn parse_input(input: &str) -> Vec<(u64, u64)> {
-    let mut rock_locations: Vec<(u64, u64)> = Vec::new();
+fn parse_input(input: &str) -> Vec<(i64, i64)> {
+    let mut rock_locations: Vec<(i64, i64)> = Vec::new();
     for line in input.lines() {
         let endpoints = line
             .split("->")
             .map(|s| s.trim())
             .map(|s| s.split(',').collect::<Vec<_>>())
-            .map(|v| (v[0].parse::<u64>().unwrap(), v[1].parse::<u64>().unwrap()))
+            .map(|v| (v[0].parse::<i64>().unwrap(), v[1].parse::<i64>().unwrap()))
             .collect::<Vec<_>>();
         for pair in endpoints.windows(2) {
             let (x1, y1) = pair[0];
@@ -45,96 +45,43 @@ fn parse_input(input: &str) -> Vec<(u64, u64)> {
     rock_locations
 }
 
-#[derive(PartialEq)]
-enum StoppingCriteria {
-    Overflow,
-    Spout,
-}
-
-use StoppingCriteria::*;
-
-impl Cave {
-    fn new(rock_locations: Vec<(u64, u64)>) -> Cave {
-        let mut objects = HashSet::new();
-        for (x, y) in rock_locations {
-            objects.insert((x as i64, y));
-        }
-        let height = objects.iter().map(|(_, y)| y).max().unwrap() + 1;
-        Cave { objects, height }
-    }
-
-    fn fill_sand(&mut self, floor: u64, criteria: StoppingCriteria) -> u64 {
-        let mut sand_count: u64 = 0;
-        let mut x: i64 = 500;
-        let mut y = 0;
-        loop {
-            match criteria {
-                Overflow => {
-                    if y + 1 >= self.height {
-                        return sand_count;
-                    }
-                }
-                Spout => {
-                    if y + 1 == self.height + floor {
-                        self.objects.insert((x, y));
-                        x = 500;
-                        y = 0;
-                    }
-                }
-            }
-            let point_below = (x, y + 1);
-            let point_diagonal_left = (x - 1, y + 1);
-            let point_diagonal_right = (x + 1, y + 1);
-
-            match self.objects.contains(&point_below) {
-                true => match self.objects.contains(&point_diagonal_left) {
-                    true => match self.objects.contains(&point_diagonal_right) {
-                        true => {
-                            sand_count += 1;
-                            if criteria == Spout && (x, y) == (500, 0) {
-                                return sand_count;
-                            }
-                            self.objects.insert((x, y));
-                            x = 500;
-                            y = 0;
-                        }
-                        false => {
-                            x += 1;
-                            y += 1;
-                        }
-                    },
-                    false => {
-                        x -= 1;
-                        y += 1;
-                    }
-                },
-                false => {
-                    y += 1;
-                }
-            }
-        }
-    }
+// The floor is two below the lowest rock
+fn with_floor(rocks: Vec<(i64, i64)>) -> Engine {
+    let floor = rocks.iter().map(|(_, y)| y).max().unwrap() + 2;
+    Engine::new(rocks, SOURCE).with_floor(floor)
 }
 
 pub fn part_one(input: &str) -> Option<u64> {
-    let rock_locations = parse_input(input);
-    let mut cave = Cave::new(rock_locations);
-    // cave.print();
-    let dropped_sand = cave.fill_sand(0, Overflow);
-    // cave.print();
-    Some(dropped_sand)
-    // None
+    let mut cave = Engine::new(parse_input(input), SOURCE);
+    // Once a grain falls past the lowest rock, all the others will too
+    let dropped_sand = cave.run(|_, outcome| outcome == Outcome::Escaped);
+    Some(dropped_sand as u64)
 }
 
 pub fn part_two(input: &str) -> Option<u64> {
-    let rock_locations = parse_input(input);
-    let mut cave = Cave::new(rock_locations);
-    let dropped_sand = cave.fill_sand(2, Spout);
-    Some(dropped_sand)
+    let mut cave = with_floor(parse_input(input));
+    // Runs until the sand piles up to the source
+    let dropped_sand = cave.run(|_, _| false);
+    Some(dropped_sand as u64)
 }
 
 fn main() {
     let input = &advent_of_code::read_file("inputs", 14);
+    // `cargo solve 14 -- --frames 500` prints the cave from part two every 500 grains
+    let mut args = pico_args::Arguments::from_env();
+    let frames: Option<usize> = args.opt_value_from_str("--frames").unwrap();
+    if let Some(every) = frames {
+        let mut cave = with_floor(parse_input(input));
+        cave.run(|cave, outcome| {
+            if let Outcome::Settled(_) = outcome {
+                if cave.settled().is_multiple_of(every) {
+                    println!("After {} grains:\n{}\n", cave.settled(), cave.frame());
+                }
+            }
+            false
+        });
+        println!("Full:\n{}\n", cave.frame());
+    }
     advent_of_code::solve!(1, part_one, input);
     advent_of_code::solve!(2, part_two, input);
 }
@@ -154,4 +101,27 @@ mod tests {
         let input = advent_of_code::read_file("examples", 14);
         assert_eq!(part_two(&input), Some(93));
     }
+
+    #[test]
+    fn test_frame() {
+        let input = advent_of_code::read_file("examples", 14);
+        let mut cave = Engine::new(parse_input(&input), SOURCE);
+        cave.run(|cave, _| cave.settled() == 5);
+        assert_eq!(
+            cave.frame(),
+            [
+                "......+...",
+                "......~...",
+                "......~...",
+                "......~...",
+                "....#.~.##",
+                "....#.~.#.",
+                "..###.~.#.",
+                ".....~o.#.",
+                "....oooo#.",
+                "#########.",
+            ]
+            .join("\n")
+        );
+    }
 }
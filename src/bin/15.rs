@@ -1,72 +1,45 @@
-use std::collections::HashSet;
-
-use itertools::Itertools;
-use regex;
+use advent_of_code::helpers::{
+    geometry::{boundary_intersections, manhattan, Diamond},
+    parse,
+    range_set::RangeSet,
+};
+
+// The puzzle asks about a different row and search area for the example than
+// for the real input, so the solutions take them as parameters and these are
+// the values for the real input.
+const ROW: i64 = 2_000_000;
+const SEARCH_BOUND: i64 = 4_000_000;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 struct Sensor {
-    x: i32,
-    y: i32,
+    x: i64,
+    y: i64,
     nearest: Beacon,
 }
 
 impl Sensor {
-    fn distance(&self, location: (i32, i32)) -> u32 {
-        ((self.x - location.0).abs() + (self.y - location.1).abs()) as u32
-    }
-    fn distance_to_nearest(&self) -> u32 {
-        self.distance((self.nearest.x, self.nearest.y))
+    fn distance_to_nearest(&self) -> i64 {
+        manhattan((self.x, self.y), (self.nearest.x, self.nearest.y))
     }
 
-    fn perimeter(&self) -> Vec<(i32, i32)> {
-        let mut points = Vec::new();
-        let distance = self.distance_to_nearest() as i32;
-        for y in 0..distance {
-            let x = distance - y;
-            points.push((self.x + x, self.y + y));
-            points.push((self.x + x, self.y - y));
-            points.push((self.x - x, self.y + y));
-            points.push((self.x - x, self.y - y));
-        }
-        // given the distance from the central point, we want to generate
-        // the points on the perimeter using manhattan distance
-        // this means we can go all the way out to the x boundaries
-        // given by `starting`, then we iterate over those values
-        // and generate the y values
-
-        points.iter().unique().cloned().collect()
+    // Every position at least as close as the nearest beacon can't hold
+    // another beacon, which is a diamond in manhattan distance
+    fn area(&self) -> Diamond {
+        Diamond::new((self.x, self.y), self.distance_to_nearest())
     }
-    fn range_given_row(&self, y: i32) -> Option<(i32, i32)> {
-        // given a row (y), return all points that are within that row
-        // using the perimeter (which only contains the boundaries)
-        let binding = self.perimeter();
-        // println!("binding: {:?}", binding);
-        let perimeter = binding
-            .iter()
-            .filter(|(_, y1)| *y1 == y)
-            .collect::<Vec<_>>();
-        // println!("perimeter: {:?}", perimeter);
-        let min_x = perimeter.iter().map(|(x, _)| x).min();
-        let max_x = perimeter.iter().map(|(x, _)| x).max();
-
-        // println!("min_x: {:?}, max_x: {:?}", min_x, max_x);
-        if let Some(min_x) = min_x {
-            if let Some(max_x) = max_x {
-                return Some((*min_x, *max_x));
-            }
-        }
-        None
+    fn range_given_row(&self, y: i64) -> Option<(i64, i64)> {
+        self.area().row(y)
     }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 struct Beacon {
-    x: i32,
-    y: i32,
+    x: i64,
+    y: i64,
 }
 
 fn parse_input(input: &str) -> (Vec<Sensor>, Vec<Beacon>) {
-    // We need to write a regex to capture the sensor and beacon locations
+    // Each line holds the sensor and beacon locations, and they're the only numbers on it
     // the input looks like this:
     // Sensor at x=2, y=18: closest beacon is at x=-2, y=15
     // Sensor at x=9, y=16: closest beacon is at x=10, y=16
@@ -75,16 +48,14 @@ fn parse_input(input: &str) -> (Vec<Sensor>, Vec<Beacon>) {
     // Sensor at x=10, y=20: closest beacon is at x=10, y=16
     // Sensor at x=14, y=17: closest beacon is at x=10, y=16
     // Sensor at x=8, y=7: closest beacon is at x=2, y=10
-    // Don't forget to capture negative numbers!
-    let sensor_re = regex::Regex::new(r"Sensor at x=(?P<sx>-?\d+), y=(?P<sy>-?\d+): closest beacon is at x=(?P<bx>-?\d+), y=(?P<by>-?\d+)").unwrap();
+    // Don't forget about negative numbers!
     let mut sensors = Vec::new();
     let mut beacons = Vec::new();
     for line in input.lines() {
-        let caps = sensor_re.captures(line).unwrap();
-        let x = caps.name("sx").unwrap().as_str().parse::<i32>().unwrap();
-        let y = caps.name("sy").unwrap().as_str().parse::<i32>().unwrap();
-        let bx = caps.name("bx").unwrap().as_str().parse::<i32>().unwrap();
-        let by = caps.name("by").unwrap().as_str().parse::<i32>().unwrap();
+        let [x, y, bx, by]: [i64; 4] = parse::integers(line)
+            .unwrap()
+            .try_into()
+            .expect("Expected four coordinates per line");
         let beacon = Beacon { x: bx, y: by };
         sensors.push(Sensor {
             x,
@@ -96,47 +67,84 @@ fn parse_input(input: &str) -> (Vec<Sensor>, Vec<Beacon>) {
     (sensors, beacons)
 }
 
-pub fn part_one(input: &str) -> Option<u32> {
-    let (sensors, beacons) = parse_input(input);
-
-    // let all_ranges = sensors
-    //     .iter()
-    //     .flat_map(|s| s.range())
-    //     .unique()
-    //     .collect::<Vec<_>>();
-    // find how many range values are in row 10
-    // Test input is row 10, real input is row 2000000
-    let beacon_positions = beacons.iter().map(|b| (b.x, b.y)).collect::<Vec<_>>();
-    let row = 10;
-    let all_ranges = sensors
-        .iter()
-        .filter_map(|s| (s.range_given_row(row)))
-        .collect::<Vec<_>>();
-    let mut row_locations: HashSet<(i32, i32)> = HashSet::new();
-    for (start, end) in all_ranges {
-        for x in start..=end {
-            if beacon_positions.contains(&(x, row)) {
-                continue;
-            }
-            row_locations.insert((x, row));
-            // println!("{}", row_locations.len())
+// All the sensors together, answering questions about which positions they
+// rule out for the distress beacon.
+struct Coverage {
+    sensors: Vec<Sensor>,
+    beacons: Vec<Beacon>,
+}
+
+impl Coverage {
+    fn new(input: &str) -> Self {
+        let (sensors, beacons) = parse_input(input);
+        Coverage { sensors, beacons }
+    }
+
+    // Positions in row `y` that are inside at least one sensor's area
+    fn row(&self, y: i64) -> RangeSet {
+        self.sensors
+            .iter()
+            .filter_map(|s| s.range_given_row(y))
+            .collect()
+    }
+
+    // Positions in row `y` where a beacon can't be. A known beacon is
+    // obviously a position where a beacon can be.
+    fn excluded_in_row(&self, y: i64) -> u64 {
+        let mut covered = self.row(y);
+        for beacon in self.beacons.iter().filter(|b| b.y == y) {
+            covered.remove(beacon.x);
         }
+        covered.len()
     }
 
-    // let total_row = all_ranges
-    //     .iter()
-    //     .filter(|(_, y)| *y == row)
-    //     .inspect(|pos| {
-    //         println!("{:?}", pos);
-    //     })
-    //     .filter(|pos| !beacon_positions.contains(pos))
-    //     .count();
+    fn is_covered(&self, position: (i64, i64)) -> bool {
+        self.sensors.iter().any(|s| s.area().contains(position))
+    }
 
-    Some(row_locations.len() as u32)
+    // The only position in `0..=bound` on both axes that no sensor covers
+    fn distress_beacon(&self, bound: i64) -> Option<(i64, i64)> {
+        let in_bounds = |(x, y): &(i64, i64)| (0..=bound).contains(x) && (0..=bound).contains(y);
+        // Since there's exactly one uncovered position, it has to be wedged
+        // between the edges of the sensor areas, right where the lines just
+        // outside of them cross
+        let areas: Vec<Diamond> = self.sensors.iter().map(|s| s.area()).collect();
+        let candidate = boundary_intersections(&areas, 1)
+            .into_iter()
+            .filter(in_bounds)
+            .find(|position| !self.is_covered(*position));
+        if candidate.is_some() {
+            return candidate;
+        }
+        // ...unless it's pushed up against the edge of the search area, in
+        // which case we sweep row by row looking for a gap
+        (0..=bound).find_map(|y| {
+            let gaps = self.row(y).gaps(0, bound);
+            gaps.ranges().first().map(|(x, _)| (*x, y))
+        })
+    }
+}
+
+fn tuning_frequency((x, y): (i64, i64)) -> u64 {
+    (x * 4_000_000 + y) as u64
 }
 
-pub fn part_two(input: &str) -> Option<u32> {
-    None
+fn count_excluded(input: &str, row: i64) -> u64 {
+    Coverage::new(input).excluded_in_row(row)
+}
+
+fn find_tuning_frequency(input: &str, bound: i64) -> Option<u64> {
+    Coverage::new(input)
+        .distress_beacon(bound)
+        .map(tuning_frequency)
+}
+
+pub fn part_one(input: &str) -> Option<u64> {
+    Some(count_excluded(input, ROW))
+}
+
+pub fn part_two(input: &str) -> Option<u64> {
+    find_tuning_frequency(input, SEARCH_BOUND)
 }
 
 fn main() {
@@ -152,12 +160,22 @@ mod tests {
     #[test]
     fn test_part_one() {
         let input = advent_of_code::read_file("examples", 15);
-        assert_eq!(part_one(&input), Some(26));
+        assert_eq!(count_excluded(&input, 10), 26);
     }
 
     #[test]
     fn test_part_two() {
         let input = advent_of_code::read_file("examples", 15);
-        assert_eq!(part_two(&input), None);
+        assert_eq!(find_tuning_frequency(&input, 20), Some(56000011));
+    }
+
+    #[test]
+    fn test_distress_beacon_on_edge() {
+        // a single sensor covering everything in 0..=4 except the corner (4, 4)
+        let input = "Sensor at x=0, y=0: closest beacon is at x=7, y=0";
+        assert_eq!(Coverage::new(input).distress_beacon(4), Some((4, 4)));
+        // and one that covers the whole search area
+        let input = "Sensor at x=2, y=2: closest beacon is at x=6, y=2";
+        assert_eq!(Coverage::new(input).distress_beacon(4), None);
     }
 }
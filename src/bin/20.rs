@@ -1,92 +1,44 @@
-use std::cmp::Ordering;
+use advent_of_code::helpers::sequence::Sequence;
 
-use num::zero;
+fn parse_input(input: &str) -> Vec<i64> {
+    input.lines().map(|line| line.parse().unwrap()).collect()
+}
 
-fn wrap_vec(vec: Vec<(u32, i64)>, index: usize) -> Vec<(u32, i64)> {
-    let (current_index, (_, value)) = vec
-        .iter()
-        .enumerate()
-        .find(|(_, (i, _))| *i as usize == index)
-        .unwrap();
-    let mut new_vec = vec.clone();
-    let item = new_vec.remove(current_index);
-    let mut new_index = (current_index as i64 + value).rem_euclid(vec.len() as i64 - 1);
-    if new_index == 0 {
-        new_index = vec.len() as i64 - 1;
+// Moves every number by its own value, in the order they appeared in the
+// file, `rounds` times over
+fn mix(numbers: Vec<i64>, rounds: usize) -> Sequence<i64> {
+    let mut sequence: Sequence<i64> = numbers.into_iter().collect();
+    for _ in 0..rounds {
+        for id in 0..sequence.len() {
+            sequence.move_by(id, *sequence.value(id));
+        }
     }
-    // let new_index_position = match (new_index.cmp(&(current_index as i64)), new_index.cmp(&0)) {
-    //     (Ordering::Less, Ordering::Less) => vec.len() as i64 + new_index,
-    //     (Ordering::Less, Ordering::Greater) => new_index,
-    //     (Ordering::Greater, Ordering::Less) => vec.len() as i64 + new_index - 1,
-    //     (Ordering::Greater, Ordering::Greater) => new_index - 1,
-    //     (_, _) => new_index,
-    // };
-
-    new_vec.insert(new_index as usize, item);
-    new_vec
+    sequence
 }
 
-fn parse_input(input: &str) -> Vec<(u32, i64)> {
-    input
-        .lines()
-        .map(|line| line.parse().unwrap())
-        .enumerate()
-        .map(|(index, value)| (index as u32, value))
-        .collect()
+// The sum of the numbers 1000, 2000 and 3000 places after the 0
+fn grove_coordinates(sequence: &Sequence<i64>) -> Option<i64> {
+    let zero = (0..sequence.len()).find(|&id| *sequence.value(id) == 0)?;
+    let zero_index = sequence.position(zero);
+    [1000, 2000, 3000]
+        .iter()
+        .map(|offset| sequence.get((zero_index + offset) % sequence.len()))
+        .sum()
 }
 
 pub fn part_one(input: &str) -> Option<i64> {
-    let coordinates = parse_input(input);
-    let mut vec = coordinates.clone();
-    for index in 0..vec.len() {
-        vec = wrap_vec(vec, index);
-        // dbg!(&coordinates[index], &vec);
-    }
-    let zero_index = vec
-        .iter()
-        .enumerate()
-        .find(|(_, (_, v))| *v == 0)
-        .unwrap()
-        .0;
-    let coordinate_sum: Vec<i64> = vec![1000, 2000, 3000]
-        .iter()
-        .map(|x: &u32| (x + zero_index as u32).rem_euclid(coordinates.len() as u32))
-        .map(|x| vec.get(x as usize).unwrap())
-        .map(|(_, x)| *x)
-        .collect::<Vec<_>>();
-    // dbg!(&coordinate_sum, &zero_index);
-    Some(coordinate_sum.iter().sum::<i64>())
+    let sequence = mix(parse_input(input), 1);
+    grove_coordinates(&sequence)
 }
 
 pub fn part_two(input: &str) -> Option<i64> {
     let decryption_key = 811589153;
-    let n_mixes = 10;
-    let mut coordinates = parse_input(input);
-    let coordinates = coordinates
-        .iter()
-        .map(|(i, x)| (*i, *x * decryption_key))
-        .collect::<Vec<_>>();
-    let mut vec = coordinates.clone();
-    for n in 0..n_mixes {
-        for index in 0..vec.len() {
-            vec = wrap_vec(vec, index);
-            // dbg!(&coordinates[index], &vec);
-        }
-    }
-    let zero_index = vec
-        .iter()
-        .enumerate()
-        .find(|(_, (_, v))| *v == 0)
-        .unwrap()
-        .0;
-    let coordinate_sum: Vec<i64> = vec![1000, 2000, 3000]
-        .iter()
-        .map(|x: &u32| (x + zero_index as u32).rem_euclid(coordinates.len() as u32))
-        .map(|x| vec.get(x as usize).unwrap())
-        .map(|(_, x)| *x)
-        .collect::<Vec<_>>();
-    // dbg!(&coordinate_sum, &zero_index);
-    Some(coordinate_sum.iter().sum::<i64>())
+    let numbers = parse_input(input)
+        .into_iter()
+        .map(|x| x * decryption_key)
+        .collect();
+    let sequence = mix(numbers, 10);
+    grove_coordinates(&sequence)
 }
 
 fn main() {
@@ -1,21 +1,41 @@
 use std::collections::HashMap;
 
-use rayon::prelude::*;
-#[derive(Debug, Clone, PartialEq)]
+use advent_of_code::helpers::expr_tree::{ExprTree, Node};
+use num::rational::Ratio;
+use num::{ToPrimitive, Zero};
 
+#[derive(Debug, Clone, PartialEq)]
 enum Operation<'a> {
     Add(&'a str, &'a str),
     Subtract(&'a str, &'a str),
     Multiply(&'a str, &'a str),
     Divide(&'a str, &'a str),
-    Equal(&'a str, &'a str),
+}
+
+impl<'a> Operation<'a> {
+    fn operands(&self) -> (&'a str, &'a str) {
+        match *self {
+            Operation::Add(left, right)
+            | Operation::Subtract(left, right)
+            | Operation::Multiply(left, right)
+            | Operation::Divide(left, right) => (left, right),
+        }
+    }
+
+    fn operator(&self) -> Operator {
+        match self {
+            Operation::Add(..) => Operator::Add,
+            Operation::Subtract(..) => Operator::Subtract,
+            Operation::Multiply(..) => Operator::Multiply,
+            Operation::Divide(..) => Operator::Divide,
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
 enum State<'a> {
     Number(u64),
     Operation(Operation<'a>),
-    Equal(bool),
 }
 
 // Parse inputs, which are the name of the monkey, followed by a colon,
@@ -37,7 +57,7 @@ enum State<'a> {
 // lgvd: ljgn * ptdq
 // drzm: hmdt - zczc
 // hmdt: 32
-fn parse_input(input: &str) -> HashMap<&str, State> {
+fn parse_input(input: &str) -> HashMap<&str, State<'_>> {
     let mut map = HashMap::new();
     for line in input.lines() {
         let mut parts = line.split(':');
@@ -65,207 +85,195 @@ fn parse_input(input: &str) -> HashMap<&str, State> {
     map
 }
 
-fn parse_input_part_two(input: &str) -> HashMap<&str, State> {
-    let mut map = HashMap::new();
-    for line in input.lines() {
-        let mut parts = line.split(':');
-        let key = parts.next().unwrap().trim();
-        let value = parts.next().unwrap().trim();
-        let value = if let Ok(number) = value.parse::<u64>() {
-            State::Number(number)
-        } else {
-            let mut parts = value.split_whitespace();
-            let left = parts.next().unwrap();
-            let operation = parts.next().unwrap();
-            let right = parts.next().unwrap();
-            let operation = match (key, operation) {
-                ("root", _) => Operation::Equal(left, right),
-                (_, "+") => Operation::Add(left, right),
-                (_, "-") => Operation::Subtract(left, right),
-                (_, "*") => Operation::Multiply(left, right),
-                (_, "/") => Operation::Divide(left, right),
-                _ => panic!("Unknown operation: {}", operation),
-            };
+// Exact fractions, so dividing never loses anything on the way to the answer
+type Value = Ratio<i128>;
 
-            State::Operation(operation)
-        };
-        map.insert(key, value);
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operator {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+}
+
+impl Operator {
+    fn apply(self, left: Value, right: Value) -> Result<Value, String> {
+        Ok(match self {
+            Operator::Add => left + right,
+            Operator::Subtract => left - right,
+            Operator::Multiply => left * right,
+            Operator::Divide if right.is_zero() => return Err("Division by zero".to_string()),
+            Operator::Divide => left / right,
+        })
     }
-    map
+
+    fn symbol(self) -> char {
+        match self {
+            Operator::Add => '+',
+            Operator::Subtract => '-',
+            Operator::Multiply => '*',
+            Operator::Divide => '/',
+        }
+    }
+}
+
+// The monkeys' calculation as a tree. Any part that doesn't depend on the
+// human is folded into a constant while the tree is built, so the only
+// operations left are the ones on the path from the root to `humn`.
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Constant(Value),
+    Human,
+    Apply(Operator, Box<Expr>, Box<Expr>),
 }
 
-fn find_human_value(mut map: HashMap<&str, State>, human_value: u64) -> Result<u64, String> {
-    let human = State::Number(human_value);
-    map.insert("humn", human.clone());
+impl Expr {
+    fn apply(operator: Operator, left: Expr, right: Expr) -> Result<Expr, String> {
+        Ok(match (left, right) {
+            (Expr::Constant(left), Expr::Constant(right)) => {
+                Expr::Constant(operator.apply(left, right)?)
+            }
+            (left, right) => Expr::Apply(operator, Box::new(left), Box::new(right)),
+        })
+    }
 
-    let mut root_value: State = map.get("root").unwrap().clone();
+    // Builds the tree below monkey `name`. If `human` is false `humn` is
+    // treated like any other monkey, shouting the number from the input.
+    fn build(map: &HashMap<&str, State>, name: &str, human: bool) -> Result<Expr, String> {
+        if human && name == "humn" {
+            return Ok(Expr::Human);
+        }
+        match map.get(name) {
+            None => Err(format!("Unknown monkey: {}", name)),
+            Some(State::Number(number)) => Ok(Expr::Constant(Value::from(*number as i128))),
+            Some(State::Operation(operation)) => {
+                let (left, right) = operation.operands();
+                Expr::apply(
+                    operation.operator(),
+                    Expr::build(map, left, human)?,
+                    Expr::build(map, right, human)?,
+                )
+            }
+        }
+    }
 
-    'outer: while matches!(root_value, State::Operation(_)) {
-        let map_copy = map.clone();
-        for (key, value) in map_copy.iter() {
-            match value {
-                State::Number(_) => {}
-                State::Operation(operation) => match operation {
-                    Operation::Add(left, right) => {
-                        let left_value = map.get(left).unwrap();
-                        let right_value = map.get(right).unwrap();
-                        if let (State::Number(left), State::Number(right)) =
-                            (left_value, right_value)
-                        {
-                            map.insert(*key, State::Number(*left + *right));
-                        }
-                    }
-                    Operation::Subtract(left, right) => {
-                        let left_value = map.get(left).unwrap();
-                        let right_value = map.get(right).unwrap();
-                        if let (State::Number(left), State::Number(right)) =
-                            (left_value, right_value)
-                        {
-                            // check if left - right is positive or would result in overflow
-                            if left < right {
-                                break 'outer;
-                            }
-                            map.insert(*key, State::Number(*left - *right));
-                        }
-                    }
-                    Operation::Multiply(left, right) => {
-                        let left_value = map.get(left).unwrap();
-                        let right_value = map.get(right).unwrap();
-                        if let (State::Number(left), State::Number(right)) =
-                            (left_value, right_value)
-                        {
-                            map.insert(*key, State::Number(*left * *right));
-                        }
+    // Finds the value of the human that makes this expression equal `target`,
+    // undoing one operation at a time on the way down to `humn`
+    fn solve(&self, mut target: Value) -> Result<Value, String> {
+        let mut expr = self;
+        loop {
+            expr = match expr {
+                Expr::Human => return Ok(target),
+                Expr::Constant(_) => return Err("Expression doesn't depend on humn".to_string()),
+                Expr::Apply(operator, left, right) => match (left.as_ref(), right.as_ref()) {
+                    // constant (op) x = target
+                    (Expr::Constant(constant), unknown) => {
+                        target = match operator {
+                            Operator::Add => target - constant,
+                            Operator::Subtract => constant - target,
+                            Operator::Multiply => Operator::Divide.apply(target, *constant)?,
+                            Operator::Divide => Operator::Divide.apply(*constant, target)?,
+                        };
+                        unknown
                     }
-                    Operation::Divide(left, right) => {
-                        let left_value = map.get(left).unwrap();
-                        let right_value = map.get(right).unwrap();
-                        if let (State::Number(left), State::Number(right)) =
-                            (left_value, right_value)
-                        {
-                            // check if left and right can be evenly divided,
-                            // as they're both integers
-                            if left % right != 0 {
-                                break 'outer;
-                            }
-                            map.insert(*key, State::Number(*left / *right));
-                        }
-                    }
-                    Operation::Equal(left, right) => {
-                        let left_value = map.get(left).unwrap();
-                        let right_value = map.get(right).unwrap();
-                        if let (State::Number(left), State::Number(right)) =
-                            (left_value, right_value)
-                        {
-                            if key != &"root" {
-                                panic!("Key not Root with Equal")
-                            }
-                            map.insert(*key, State::Equal(*left == *right));
-                        }
+                    // x (op) constant = target
+                    (unknown, Expr::Constant(constant)) => {
+                        target = match operator {
+                            Operator::Add => target - constant,
+                            Operator::Subtract => target + constant,
+                            Operator::Multiply => Operator::Divide.apply(target, *constant)?,
+                            Operator::Divide => target * constant,
+                        };
+                        unknown
                     }
+                    _ => return Err("humn appears more than once".to_string()),
                 },
-                State::Equal(_) => break 'outer,
             }
         }
-        root_value = map.get("root").unwrap().clone();
     }
+}
+
+fn to_u64(value: Value) -> Option<u64> {
+    if value.is_integer() {
+        value.to_integer().to_u64()
+    } else {
+        None
+    }
+}
 
-    // if root-value is an equal true state,
-    // return human value
+// The monkeys as named expressions, for dumping. The root monkey checks
+// whether its operands are equal, so it shows up as '='.
+fn expr_tree(map: &HashMap<&str, State>) -> ExprTree {
+    map.iter()
+        .map(|(name, state)| {
+            let node = match state {
+                State::Number(number) => Node::Leaf(number.to_string()),
+                State::Operation(operation) => {
+                    let (left, right) = operation.operands();
+                    let symbol = match *name {
+                        "root" => '=',
+                        _ => operation.operator().symbol(),
+                    };
+                    Node::Operation(left.to_string(), symbol, right.to_string())
+                }
+            };
+            (name.to_string(), node)
+        })
+        .collect()
+}
 
-    match root_value {
-        State::Equal(true) => Ok(human_value),
-        _ => Err(format!("No human value found for {}", human_value)),
+// The equation the root monkey checks in part two, with `humn` as the unknown
+fn equation(tree: &ExprTree) -> Result<String, String> {
+    match tree.get("root") {
+        Some(Node::Operation(left, _, right)) => Ok(format!(
+            "{} = {}",
+            tree.infix(left, "humn")?,
+            tree.infix(right, "humn")?
+        )),
+        _ => Err("The root monkey doesn't compare anything".to_string()),
     }
 }
 
 pub fn part_one(input: &str) -> Option<u64> {
-    let mut map = parse_input(input);
-    let mut root_value: &State = map.get("root").unwrap();
-    while matches!(root_value, State::Operation(_)) {
-        let map_copy = map.clone();
-        for (key, value) in map_copy.iter() {
-            match value {
-                State::Number(_) => {}
-                State::Operation(operation) => match operation {
-                    Operation::Add(left, right) => {
-                        let left_value = map.get(left).unwrap();
-                        let right_value = map.get(right).unwrap();
-                        if let (State::Number(left), State::Number(right)) =
-                            (left_value, right_value)
-                        {
-                            map.insert(*key, State::Number(*left + *right));
-                        }
-                    }
-                    Operation::Subtract(left, right) => {
-                        let left_value = map.get(left).unwrap();
-                        let right_value = map.get(right).unwrap();
-                        if let (State::Number(left), State::Number(right)) =
-                            (left_value, right_value)
-                        {
-                            map.insert(*key, State::Number(*left - *right));
-                        }
-                    }
-                    Operation::Multiply(left, right) => {
-                        let left_value = map.get(left).unwrap();
-                        let right_value = map.get(right).unwrap();
-                        if let (State::Number(left), State::Number(right)) =
-                            (left_value, right_value)
-                        {
-                            map.insert(*key, State::Number(*left * *right));
-                        }
-                    }
-                    Operation::Divide(left, right) => {
-                        let left_value = map.get(left).unwrap();
-                        let right_value = map.get(right).unwrap();
-                        if let (State::Number(left), State::Number(right)) =
-                            (left_value, right_value)
-                        {
-                            map.insert(*key, State::Number(*left / *right));
-                        }
-                    }
-                    _ => panic!("Equal in Part 1"),
-                },
-                _ => panic!("Unknown state in Part 1"),
-            }
-        }
-        root_value = map.get("root").unwrap();
+    let map = parse_input(input);
+    match Expr::build(&map, "root", false).ok()? {
+        Expr::Constant(value) => to_u64(value),
+        _ => None,
     }
-    Some(match root_value {
-        State::Number(number) => *number,
-        State::Operation(_) => panic!("Root value is still an operation"),
-        State::Equal(_) => panic!("Root value is still an operation"),
-    })
 }
 
 pub fn part_two(input: &str) -> Option<u64> {
-    let map = parse_input_part_two(input);
-    let mut start_value = 1;
-    loop {
-        let map_clone = map.clone();
-        match find_human_value(map_clone, start_value) {
-            Ok(human_value) => return Some(human_value),
-            Err(_) => start_value += 1,
-        }
-        if start_value % 100_000_000 == 0 {
-            println!("Start value: {}", start_value);
+    let map = parse_input(input);
+    // The root monkey checks whether both of its operands are equal
+    let (left, right) = match map.get("root")? {
+        State::Operation(operation) => operation.operands(),
+        State::Number(_) => return None,
+    };
+    let left = Expr::build(&map, left, true).ok()?;
+    let right = Expr::build(&map, right, true).ok()?;
+    let human = match (&left, &right) {
+        (Expr::Constant(target), expr) | (expr, Expr::Constant(target)) => {
+            expr.solve(*target).ok()?
         }
-    }
-    // (0..u64::MAX).into_par_iter().for_each(|start_value| {
-    //     let map_clone = map.clone();
-    //     match find_human_value(map_clone, start_value) {
-    //         Ok(human_value) => return Some(human_value),
-    //         Err(_) => start_value += 1,
-    //     }
-    //     if start_value % 1000 == 0 {
-    //         println!("Start value: {}", start_value);
-    //     }
-    // });
-    // None
+        _ => return None,
+    };
+    to_u64(human)
 }
 
 fn main() {
     let input = &advent_of_code::read_file("inputs", 21);
+    // `cargo solve 21 -- --dump infix` prints the equation from part two,
+    // `--dump dot` the monkey graph for `dot -Tsvg`
+    let mut args = pico_args::Arguments::from_env();
+    let dump: Option<String> = args.opt_value_from_str("--dump").unwrap();
+    if let Some(format) = dump {
+        let tree = expr_tree(&parse_input(input));
+        match format.as_str() {
+            "infix" => println!("{}", equation(&tree).unwrap()),
+            "dot" => print!("{}", tree.to_dot("monkeys", "humn")),
+            _ => panic!("Unknown dump format {}, expected infix or dot", format),
+        }
+    }
     advent_of_code::solve!(1, part_one, input);
     advent_of_code::solve!(2, part_two, input);
 }
@@ -285,4 +293,37 @@ mod tests {
         let input = advent_of_code::read_file("examples", 21);
         assert_eq!(part_two(&input), Some(301));
     }
+
+    #[test]
+    fn test_dump() {
+        let input = advent_of_code::read_file("examples", 21);
+        let tree = expr_tree(&parse_input(&input));
+        assert_eq!(
+            equation(&tree),
+            Ok("((4 + (2 * (humn - 3))) / 4) = ((32 - 2) * 5)".to_string())
+        );
+        let mut dependent: Vec<String> = tree.dependents("humn").into_iter().collect();
+        dependent.sort_unstable();
+        assert_eq!(
+            dependent,
+            vec!["cczh", "humn", "lgvd", "pppw", "ptdq", "root"]
+        );
+        let dot = tree.to_dot("monkeys", "humn").to_string();
+        assert!(dot.contains("\"humn\" [label=\"humn: 5\", style=\"filled\", fillcolor=\"red\"];"));
+        assert!(dot.contains("\"root\" [label=\"root: pppw = sjmn\""));
+        assert!(dot.contains("\"sjmn\" -> \"drzm\";"));
+    }
+
+    #[test]
+    fn test_solve_with_fractions() {
+        // (2 - humn) / 4 = 1/2 only has a solution if the division is exact
+        let input = "root: half + lhs\nhalf: one / two\none: 1\ntwo: 2\nlhs: sub / four\nsub: two - humn\nfour: 4\nhumn: 0";
+        assert_eq!(part_two(input), Some(0));
+        let map = parse_input(input);
+        let lhs = Expr::build(&map, "lhs", true).unwrap();
+        assert_eq!(lhs.solve(Value::new(3, 4)), Ok(Value::from(-1)));
+        assert_eq!(lhs.solve(Value::new(1, 3)), Ok(Value::new(2, 3)));
+        assert!(Expr::build(&map, "root", false).is_ok());
+        assert!(Expr::build(&parse_input("root: a / b\na: 1\nb: 0"), "root", false).is_err());
+    }
 }
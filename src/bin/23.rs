@@ -1,7 +1,20 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
+use advent_of_code::helpers::bit_grid::BitGrid;
 use glam::IVec2;
 
+// y grows downwards, so north is -y
+const NEIGHBOURS: [IVec2; 8] = [
+    IVec2::new(0, -1),  // N
+    IVec2::new(1, -1),  // NE
+    IVec2::new(1, 0),   // E
+    IVec2::new(1, 1),   // SE
+    IVec2::new(0, 1),   // S
+    IVec2::new(-1, 1),  // SW
+    IVec2::new(-1, 0),  // W
+    IVec2::new(-1, -1), // NW
+];
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 enum Direction {
     North,
@@ -10,68 +23,305 @@ enum Direction {
     East,
 }
 
-// We need to implement a next_direction for Direction so that we can
-// cycle through them in the order we've declared them. The order is
-// North, South, West, East
 impl Direction {
-    fn next_direction(&self) -> Direction {
+    // The order the elves consider the directions in during the first round.
+    // Every round the first one moves to the back.
+    const PUZZLE_ORDER: [Direction; 4] = [
+        Direction::North,
+        Direction::South,
+        Direction::West,
+        Direction::East,
+    ];
+
+    fn step(&self) -> IVec2 {
         match self {
-            Direction::North => Direction::South,
-            Direction::South => Direction::West,
-            Direction::West => Direction::East,
-            Direction::East => Direction::North,
+            Direction::North => NEIGHBOURS[0],
+            Direction::East => NEIGHBOURS[2],
+            Direction::South => NEIGHBOURS[4],
+            Direction::West => NEIGHBOURS[6],
         }
     }
-    fn comparison_vectors(&self) -> Vec<IVec2> {
+
+    // The neighbours (as indices into `NEIGHBOURS`) that have to be empty to
+    // move this way: the direction itself and its two diagonals
+    fn blocked_by(&self) -> [usize; 3] {
         match self {
-            Direction::North => vec![IVec2::new(0, -1), IVec2::new(1, -1), IVec2::new(-1, -1)],
-            Direction::South => vec![IVec2::new(0, 1), IVec2::new(1, 1), IVec2::new(-1, 1)],
-            Direction::West => vec![IVec2::new(-1, 0), IVec2::new(-1, 1), IVec2::new(-1, -1)],
-            Direction::East => vec![IVec2::new(1, 0), IVec2::new(1, 1), IVec2::new(1, -1)],
+            Direction::North => [7, 0, 1],
+            Direction::East => [1, 2, 3],
+            Direction::South => [3, 4, 5],
+            Direction::West => [5, 6, 7],
         }
     }
-    fn direction_cycle(&self) -> Vec<Direction> {
-        let mut directions = vec![*self];
-        let mut next_direction = self.next_direction();
-        while next_direction != *self {
-            directions.push(next_direction);
-            next_direction = next_direction.next_direction();
+}
+
+// How much empty ground to add around the elves whenever one of them reaches
+// the edge of the grid
+const MARGIN: usize = 16;
+
+// How to keep track of where the elves are
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Backend {
+    // Just the elves' positions. Every elf looks up its neighbours, and the
+    // proposals for each target are counted in a map.
+    HashSet,
+    // Every cell around the elves, one bit each. A round is worked out for all
+    // elves at once with shifted copies of the grid.
+    BitGrid,
+}
+
+#[derive(Debug, Clone)]
+enum Elves {
+    Sparse(HashSet<IVec2>),
+    // `origin` is the position of the top left cell of the grid
+    Packed { grid: BitGrid, origin: IVec2 },
+}
+
+impl Elves {
+    fn new(backend: Backend, elves: Vec<IVec2>) -> Self {
+        match backend {
+            Backend::HashSet => Elves::Sparse(elves.into_iter().collect()),
+            Backend::BitGrid => {
+                let min = elves.iter().copied().reduce(IVec2::min).unwrap_or_default();
+                let max = elves.iter().copied().reduce(IVec2::max).unwrap_or_default();
+                let size = max - min + IVec2::ONE;
+                let mut grid = BitGrid::new(size.x as usize, size.y as usize);
+                for elf in elves {
+                    let cell = elf - min;
+                    grid.set(cell.x as usize, cell.y as usize, true);
+                }
+                Elves::Packed {
+                    grid: grid.pad(MARGIN),
+                    origin: min - IVec2::splat(MARGIN as i32),
+                }
+            }
+        }
+    }
+
+    fn contains(&self, elf: IVec2) -> bool {
+        match self {
+            Elves::Sparse(elves) => elves.contains(&elf),
+            Elves::Packed { grid, origin } => {
+                let cell = elf - *origin;
+                grid.get(cell.x as isize, cell.y as isize)
+            }
+        }
+    }
+
+    fn positions(&self) -> Vec<IVec2> {
+        match self {
+            Elves::Sparse(elves) => elves.iter().copied().collect(),
+            Elves::Packed { grid, origin } => grid
+                .iter()
+                .map(|(x, y)| *origin + IVec2::new(x as i32, y as i32))
+                .collect(),
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            Elves::Sparse(elves) => elves.len(),
+            Elves::Packed { grid, .. } => grid.count_ones(),
+        }
+    }
+
+    // Moves the elves once, trying the directions in `order`, and returns how
+    // many of them moved
+    fn spread(&mut self, order: &[Direction]) -> usize {
+        match self {
+            Elves::Sparse(elves) => Elves::spread_sparse(elves, order),
+            Elves::Packed { grid, origin } => Elves::spread_packed(grid, origin, order),
         }
-        directions
     }
+
+    fn spread_sparse(elves: &mut HashSet<IVec2>, order: &[Direction]) -> usize {
+        // Where each elf would like to go, if it wants to move at all
+        let proposals: Vec<(IVec2, IVec2)> = elves
+            .iter()
+            .filter_map(|&elf| {
+                let crowded: Vec<bool> = NEIGHBOURS
+                    .iter()
+                    .map(|offset| elves.contains(&(elf + *offset)))
+                    .collect();
+                if !crowded.contains(&true) {
+                    return None;
+                }
+                order
+                    .iter()
+                    .find(|direction| direction.blocked_by().iter().all(|&i| !crowded[i]))
+                    .map(|direction| (elf, elf + direction.step()))
+            })
+            .collect();
+        let mut counts: HashMap<IVec2, usize> = HashMap::new();
+        for (_, target) in &proposals {
+            *counts.entry(*target).or_default() += 1;
+        }
+        // Elves only move if nobody else wants to go to the same place
+        let mut moved = 0;
+        for (elf, target) in proposals {
+            if counts[&target] == 1 {
+                elves.remove(&elf);
+                elves.insert(target);
+                moved += 1;
+            }
+        }
+        moved
+    }
+
+    fn spread_packed(grid: &mut BitGrid, origin: &mut IVec2, order: &[Direction]) -> usize {
+        let (width, height) = (grid.width() as isize, grid.height() as isize);
+        // Elves move at most one step, so they can't leave a grid they aren't
+        // at the edge of
+        if (0..width).any(|x| grid.get(x, 0) || grid.get(x, height - 1))
+            || (0..height).any(|y| grid.get(0, y) || grid.get(width - 1, y))
+        {
+            *grid = grid.pad(MARGIN);
+            *origin -= IVec2::splat(MARGIN as i32);
+        }
+        let (width, height) = (grid.width(), grid.height());
+        // Bit (x, y) of neighbours[i] is set if there's an elf at
+        // `NEIGHBOURS[i]` from (x, y)
+        let neighbours: Vec<BitGrid> = NEIGHBOURS
+            .iter()
+            .map(|offset| grid.neighbour(offset.x as isize, offset.y as isize))
+            .collect();
+        let crowded = neighbours
+            .iter()
+            .fold(BitGrid::new(width, height), |crowded, grid| &crowded | grid);
+        let mut undecided = &*grid & &crowded;
+        // Where the elves that want to go each way would end up
+        let mut targets = Vec::with_capacity(order.len());
+        for direction in order {
+            let blocked = direction
+                .blocked_by()
+                .iter()
+                .fold(BitGrid::new(width, height), |blocked, &i| {
+                    &blocked | &neighbours[i]
+                });
+            let proposing = undecided.and_not(&blocked);
+            undecided = undecided.and_not(&proposing);
+            let step = direction.step();
+            targets.push((step, proposing.shift(step.x as isize, step.y as isize)));
+        }
+        // Elves only move if nobody else wants to go to the same place
+        let mut proposed = BitGrid::new(width, height);
+        let mut contested = BitGrid::new(width, height);
+        for (_, target) in &targets {
+            contested = &contested | &(&proposed & target);
+            proposed = &proposed | target;
+        }
+        let mut moved = 0;
+        for (step, target) in &targets {
+            let arriving = target.and_not(&contested);
+            moved += arriving.count_ones();
+            let leaving = arriving.shift(-step.x as isize, -step.y as isize);
+            *grid = &grid.and_not(&leaving) | &arriving;
+        }
+        moved
+    }
+}
+
+// The elves spreading out over the grove, one round at a time
+#[derive(Debug, Clone)]
+struct Diffusion {
+    elves: Elves,
+    order: Vec<Direction>,
+    rounds: usize,
+    verbose: bool,
 }
 
-// For an elves location, check whether there is another elf in the neighboring positoins
-// For example, if the direction is North, check whether there is an elf in the N, NE, or NW position
-// relative to that elf's location. If there is an elf in that position, check the next direction. If
-// there aren't any neighbors in that direction, return the position they would move to if the headed
-// in that cardinal direction. If there are no available moves, because that elf is surrounded,
-// return None. Remember for each direction we need to check that direction and the two neighboring diagonals.
-// Also remember that if we cycle throug all directions and this elf can't go anywhere, return None.
-fn check_direction(elf: &IVec2, direction: &Direction, locations: &[IVec2]) -> Option<IVec2> {
-    // check if there are no neighbors surrounding this elf's position
-    // Check in all directions first
-    let any_direction = direction
-        .direction_cycle()
-        .iter()
-        .flat_map(|d| d.comparison_vectors())
-        .any(|v| locations.contains(&(*elf + v)));
-    if !any_direction {
-        return None;
+impl Diffusion {
+    fn new(elves: impl IntoIterator<Item = IVec2>) -> Self {
+        Self {
+            elves: Elves::new(Backend::BitGrid, elves.into_iter().collect()),
+            order: Direction::PUZZLE_ORDER.to_vec(),
+            rounds: 0,
+            verbose: false,
+        }
+    }
+
+    fn with_backend(mut self, backend: Backend) -> Self {
+        self.elves = Elves::new(backend, self.elves.positions());
+        self
+    }
+
+    fn with_order(mut self, order: &[Direction]) -> Self {
+        self.order = order.to_vec();
+        self
+    }
+
+    // Prints the grove after every round
+    fn verbose(mut self, verbose: bool) -> Self {
+        self.verbose = verbose;
+        self
+    }
+
+    fn contains(&self, elf: IVec2) -> bool {
+        self.elves.contains(elf)
+    }
+
+    // Plays a round and returns how many elves moved
+    fn round(&mut self) -> usize {
+        let moved = self.elves.spread(&self.order);
+        self.order.rotate_left(1);
+        self.rounds += 1;
+        if self.verbose {
+            println!(
+                "== End of Round {} ({} moved) ==\n{}\n",
+                self.rounds,
+                moved,
+                self.render()
+            );
+        }
+        moved
     }
-    let directions = direction.direction_cycle();
-    'direction: for direction in directions {
-        let comparison_vectors = direction.comparison_vectors();
-        for comparison_vector in comparison_vectors {
-            let comparison_location = *elf + comparison_vector;
-            match locations.contains(&comparison_location) {
-                true => continue 'direction,
-                false => {}
+
+    fn run(&mut self, rounds: usize) {
+        for _ in 0..rounds {
+            self.round();
+        }
+    }
+
+    // The number of the first round in which no elf moves
+    fn rounds_until_stable(&mut self) -> usize {
+        while self.round() > 0 {}
+        self.rounds
+    }
+
+    // The corners of the smallest rectangle holding every elf, if there are any
+    fn bounds(&self) -> Option<(IVec2, IVec2)> {
+        let positions = self.elves.positions();
+        let min = positions.iter().copied().reduce(IVec2::min)?;
+        let max = positions.iter().copied().reduce(IVec2::max)?;
+        Some((min, max))
+    }
+
+    // Empty tiles in the smallest rectangle holding every elf
+    fn empty_ground(&self) -> usize {
+        match self.bounds() {
+            Some((min, max)) => {
+                let size = max - min + IVec2::ONE;
+                (size.x * size.y) as usize - self.elves.len()
             }
+            None => 0,
         }
-        return Some(*elf + direction.comparison_vectors()[0]);
     }
-    None
+
+    fn render(&self) -> String {
+        let Some((min, max)) = self.bounds() else {
+            return String::new();
+        };
+        let rows: Vec<String> = (min.y..=max.y)
+            .map(|y| {
+                (min.x..=max.x)
+                    .map(|x| match self.contains(IVec2::new(x, y)) {
+                        true => '#',
+                        false => '.',
+                    })
+                    .collect()
+            })
+            .collect();
+        rows.join("\n")
+    }
 }
 
 // The input is a grid of locations of elves, represented with
@@ -98,170 +348,52 @@ fn parse(input: &str) -> Vec<IVec2> {
         .collect()
 }
 
-fn get_boundaries(locations: &Vec<IVec2>) -> (IVec2, IVec2) {
-    let mut min = IVec2::new(std::i32::MAX, std::i32::MAX);
-    let mut max = IVec2::new(std::i32::MIN, std::i32::MIN);
-    for location in locations {
-        if location.x < min.x {
-            min.x = location.x;
-        }
-        if location.y < min.y {
-            min.y = location.y;
-        }
-        if location.x > max.x {
-            max.x = location.x;
-        }
-        if location.y > max.y {
-            max.y = location.y;
-        }
-    }
-    (min, max)
-}
-
-fn get_size(corners: (IVec2, IVec2)) -> (u32, u32) {
-    let (min, max) = corners;
-    ((max.x - min.x + 1) as u32, (max.y - min.y + 1) as u32)
-}
-
-fn display_vectors(vectors: &Vec<IVec2>) {
-    let (min, max) = get_boundaries(vectors);
-    let (width, height) = get_size((min, max));
-    let mut grid = vec![vec!['.'; width as usize]; height as usize];
-    // draw the grid. remember that the coordinates are signed integers,
-    // but the grid must be created with unsigned integers, so we have to do
-    // some math to "shift" the grid to the right place
-    for vector in vectors {
-        grid[(vector.y - min.y) as usize][(vector.x - min.x) as usize] = '#';
-    }
-    for row in grid {
-        println!("{}", row.iter().collect::<String>());
-    }
-}
-
 pub fn part_one(input: &str) -> Option<u32> {
-    let starting_locations = parse(input);
-    let rounds = 10;
-    let mut direction_cycle = vec![
-        Direction::North,
-        Direction::South,
-        Direction::West,
-        Direction::East,
-    ]
-    .into_iter()
-    .cycle();
-    let mut locations = starting_locations;
-    let mut round_direction = direction_cycle.next().unwrap();
-    for round in 0..rounds {
-        let mut proposed_locations: HashMap<IVec2, IVec2> = locations
-            .iter()
-            .filter_map(|location| {
-                check_direction(location, &round_direction, &locations)
-                    .map(|proposed_location| (*location, proposed_location))
-            })
-            .collect();
-        // dbg!(&proposed_locations);
-        // if there are duplicates in proposed locations, the elves at those positions don't move
-        // identify the duplicates and remove them from the proposed locations
-        let mut duplicate_locations = vec![];
-        for (location, proposed_location) in proposed_locations.iter() {
-            if proposed_locations
-                .values()
-                .filter(|x| x == &proposed_location)
-                .count()
-                > 1
-            {
-                duplicate_locations.push(*location);
-            }
-        }
-        // dbg!(&duplicate_locations);
-        for duplicate_location in duplicate_locations {
-            proposed_locations.remove(&duplicate_location);
-        }
-        // update the locations by moving any elf that has a proposed location to that new location
-        for (location, proposed_location) in proposed_locations.iter() {
-            locations.retain(|x| x != location);
-            locations.push(*proposed_location);
-        }
-        round_direction = direction_cycle.next().unwrap();
-        println!("round: {}", round);
-        display_vectors(&locations);
-        println!("size: {}", {
-            let (min, max) = get_boundaries(&locations);
-            let (width, height) = get_size((min, max));
-            width * height
-        });
-    }
-
-    Some(
-        {
-            let (min, max) = get_boundaries(&locations);
-            let (width, height) = get_size((min, max));
-            width * height
-        } - locations.len() as u32,
-    )
+    let mut grove = Diffusion::new(parse(input));
+    grove.run(10);
+    Some(grove.empty_ground() as u32)
 }
 
 pub fn part_two(input: &str) -> Option<u32> {
-    let starting_locations = parse(input);
-    let mut direction_cycle = vec![
-        Direction::North,
-        Direction::South,
-        Direction::West,
-        Direction::East,
-    ]
-    .into_iter()
-    .cycle();
-    let mut locations = starting_locations;
-    let mut round_direction = direction_cycle.next().unwrap();
-    let mut no_proposed_locations = false;
-    let mut round = 0;
-    while !no_proposed_locations {
-        let mut proposed_locations: HashMap<IVec2, IVec2> = locations
-            .iter()
-            .filter_map(|location| {
-                check_direction(location, &round_direction, &locations)
-                    .map(|proposed_location| (*location, proposed_location))
-            })
-            .collect();
-        no_proposed_locations = proposed_locations.is_empty();
-        println!(
-            "round: {}, proposed_locations: {}",
-            round,
-            proposed_locations.len()
-        );
-
-        // dbg!(&proposed_locations);
-        // if there are duplicates in proposed locations, the elves at those positions don't move
-        // identify the duplicates and remove them from the proposed locations
-        let mut duplicate_locations = vec![];
-        for (location, proposed_location) in proposed_locations.iter() {
-            if proposed_locations
-                .values()
-                .filter(|x| x == &proposed_location)
-                .count()
-                > 1
-            {
-                duplicate_locations.push(*location);
-            }
-        }
-        // dbg!(&duplicate_locations);
-        for duplicate_location in duplicate_locations {
-            proposed_locations.remove(&duplicate_location);
-        }
-        // update the locations by moving any elf that has a proposed location to that new location
-        for (location, proposed_location) in proposed_locations.iter() {
-            locations.retain(|x| x != location);
-            locations.push(*proposed_location);
-        }
-        round_direction = direction_cycle.next().unwrap();
-        round += 1;
-    }
-
-    Some(round as u32)
+    let mut grove = Diffusion::new(parse(input));
+    Some(grove.rounds_until_stable() as u32)
 }
 
 fn main() {
     let input = &advent_of_code::read_file("inputs", 23);
+    // `cargo solve 23 -- --verbose` prints the grove after every round of part two,
+    // `--order ENWS` changes the order the elves try the directions in and
+    // `--backend hashset` keeps track of the elves in a hash set instead of a grid
+    let mut args = pico_args::Arguments::from_env();
+    let verbose = args.contains("--verbose");
+    let order: Option<String> = args.opt_value_from_str("--order").unwrap();
+    let backend: Option<String> = args.opt_value_from_str("--backend").unwrap();
+    if verbose || order.is_some() || backend.is_some() {
+        let backend = match backend.as_deref() {
+            None | Some("bitgrid") => Backend::BitGrid,
+            Some("hashset") => Backend::HashSet,
+            Some(other) => panic!("Unknown backend {}, expected bitgrid or hashset", other),
+        };
+        let order: Vec<Direction> = match order {
+            Some(order) => order
+                .chars()
+                .map(|c| match c {
+                    'N' => Direction::North,
+                    'S' => Direction::South,
+                    'W' => Direction::West,
+                    'E' => Direction::East,
+                    _ => panic!("Unknown direction {}", c),
+                })
+                .collect(),
+            None => Direction::PUZZLE_ORDER.to_vec(),
+        };
+        let rounds = Diffusion::new(parse(input))
+            .with_backend(backend)
+            .with_order(&order)
+            .verbose(verbose)
+            .rounds_until_stable();
+        println!("Stable after {} rounds", rounds);
+    }
     advent_of_code::solve!(1, part_one, input);
     advent_of_code::solve!(2, part_two, input);
 }
@@ -281,4 +413,39 @@ mod tests {
         let input = advent_of_code::read_file("examples", 23);
         assert_eq!(part_two(&input), Some(20));
     }
+
+    #[test]
+    fn test_small_example() {
+        let mut grove = Diffusion::new(parse(".....\n..##.\n..#..\n.....\n..##.\n....."));
+        assert_eq!(grove.round(), 3);
+        assert_eq!(grove.render(), "##\n..\n#.\n.#\n#.");
+        assert_eq!(grove.rounds_until_stable(), 4);
+        assert_eq!(grove.render(), "..#..\n....#\n#....\n....#\n.....\n..#..");
+        // With east first the elves step aside instead of apart
+        let mut grove =
+            Diffusion::new(parse("#\n#")).with_order(&[Direction::East, Direction::North]);
+        assert_eq!(grove.round(), 2);
+        assert!(grove.contains(IVec2::new(1, 0)));
+        assert!(grove.contains(IVec2::new(1, 1)));
+    }
+
+    #[test]
+    fn test_backends_agree() {
+        let input = advent_of_code::read_file("examples", 23);
+        let mut packed = Diffusion::new(parse(&input));
+        let mut sparse = Diffusion::new(parse(&input)).with_backend(Backend::HashSet);
+        for _ in 0..10 {
+            assert_eq!(packed.round(), sparse.round());
+            assert_eq!(packed.render(), sparse.render());
+        }
+        assert_eq!(sparse.empty_ground(), 110);
+        assert_eq!(sparse.rounds_until_stable(), 20);
+    }
+
+    #[test]
+    fn test_no_elves() {
+        assert_eq!(part_one("...\n..."), Some(0));
+        assert_eq!(part_one(""), Some(0));
+        assert_eq!(Diffusion::new(vec![]).render(), "");
+    }
 }
@@ -1,15 +1,11 @@
-use std::{
-    collections::{HashMap, HashSet},
-    fmt::Display,
-};
+use std::{fmt::Display, str::FromStr};
 
-use glam::IVec2;
-use num::integer::lcm;
-use petgraph::{
-    algo::{astar, dijkstra, has_path_connecting},
-    dot::{Config, Dot},
-    prelude::DiGraphMap,
+use advent_of_code::helpers::{
+    bit_grid::BitGrid,
+    cycle::{brent, Cycle},
+    search::TimedSearch,
 };
+use glam::IVec2;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 enum Direction {
@@ -30,6 +26,13 @@ impl Display for Direction {
     }
 }
 impl Direction {
+    const ALL: [Direction; 4] = [
+        Direction::Up,
+        Direction::Down,
+        Direction::Left,
+        Direction::Right,
+    ];
+
     fn unit_vector(&self) -> IVec2 {
         use Direction::*;
         match self {
@@ -39,536 +42,196 @@ impl Direction {
             Right => IVec2::new(1, 0),
         }
     }
-    fn step(&self, max_x: i32, max_y: i32, position: IVec2) -> IVec2 {
-        let mut next_position = position + self.unit_vector();
-        // Check if we're at the edge of the map, if we are, wrap around to the
-        // other side.
-        match &self {
-            Direction::Up => {
-                if next_position.y < 0 {
-                    next_position.y = max_y;
-                }
-            }
-            Direction::Down => {
-                if next_position.y > max_y {
-                    next_position.y = 0;
-                }
-            }
-            Direction::Left => {
-                if next_position.x < 0 {
-                    next_position.x = max_x;
-                }
-            }
-            Direction::Right => {
-                if next_position.x > max_x {
-                    next_position.x = 0;
-                }
-            }
-        }
-        next_position
-    }
 }
 
-struct Blizzards(Vec<(Direction, IVec2)>);
-struct Walls(HashSet<IVec2>);
-
-impl Blizzards {
-    fn step(&self, walls: &Walls) -> Self {
-        use Direction::*;
-        let mut new_blizzards: Vec<(Direction, IVec2)> = Vec::new();
-        let (max_x, max_y) = walls.0.iter().fold((0, 0), |(max_x, max_y), pos| {
-            (max_x.max(pos.x), max_y.max(pos.y))
-        });
-        for (direction, position) in self.0.iter() {
-            // Propose a move to the next step. Check if there's a wall one space
-            // in the next direction, if there is, wrap around to the other side.
-            let next_position = match direction {
-                Up => {
-                    let mut proposed_position = direction.step(max_x, max_y, *position);
-                    while walls.0.contains(&proposed_position) {
-                        proposed_position = direction.step(max_x, max_y, proposed_position);
-                    }
-                    proposed_position
-                }
-                Down => {
-                    let mut proposed_position = direction.step(max_x, max_y, *position);
-                    while walls.0.contains(&proposed_position) {
-                        proposed_position = direction.step(max_x, max_y, proposed_position);
-                    }
-                    proposed_position
-                }
-                Left => {
-                    let mut proposed_position = direction.step(max_x, max_y, *position);
-                    while walls.0.contains(&proposed_position) {
-                        proposed_position = direction.step(max_x, max_y, proposed_position);
-                    }
-                    proposed_position
-                }
-                Right => {
-                    let mut proposed_position = direction.step(max_x, max_y, *position);
-                    while walls.0.contains(&proposed_position) {
-                        proposed_position = direction.step(max_x, max_y, proposed_position);
-                    }
-                    proposed_position
-                }
-            };
-            new_blizzards.push((*direction, next_position));
-        }
-        Self(new_blizzards)
-    }
-}
-
-impl Walls {
-    fn boundaries(&self) -> (i32, i32) {
-        self.0.iter().fold((0, 0), |(max_x, max_y), pos| {
-            (max_x.max(pos.x), max_y.max(pos.y))
-        })
-    }
+// The valley, in coordinates that include the walls around it.
+//
+// A blizzard keeps going in a straight line and wraps around inside the walls,
+// so where it is at any minute follows from where it started: one heading right
+// from column x is in column (x + minute) mod width of the same row. Instead of
+// moving blizzards around we look back from a cell to where any blizzard that's
+// on it now would have started, which makes any minute as cheap as any other.
+struct Basin {
+    // The size of the area inside the walls
+    width: i32,
+    height: i32,
+    start: IVec2,
+    goal: IVec2,
+    // Where the blizzards heading each way are at minute 0, inside the walls
+    lanes: Vec<(Direction, BitGrid)>,
+    // The blizzards are all back where they started every `cycle.period` minutes
+    cycle: Cycle,
 }
 
 // Input is either a wall '#' or a blizzard '<', '>', '^', or 'v'.
-// Empty space is a '.'
-// Example:
+// Empty space is a '.'. The only gaps in the walls are the start, at the top,
+// and the goal, at the bottom. Example:
 // #.######
 // #>>.<^<#
 // #.<..<<#
 // #>v.><>#
 // #<^v^^>#
 // ######.#
-fn parse(input: &str) -> (Blizzards, Walls) {
-    let mut blizzards: Vec<(Direction, IVec2)> = Vec::new();
-    let mut walls: HashSet<IVec2> = HashSet::new();
-    for (y, line) in input.lines().enumerate() {
-        for (x, c) in line.chars().enumerate() {
-            let position = IVec2::new(x as i32, y as i32);
-            match c {
-                '#' => {
-                    walls.insert(position);
-                }
-                '>' => {
-                    blizzards.push((Direction::Right, position));
-                }
-                '<' => {
-                    blizzards.push((Direction::Left, position));
-                }
-                '^' => {
-                    blizzards.push((Direction::Up, position));
-                }
-                'v' => {
-                    blizzards.push((Direction::Down, position));
-                }
-                _ => {}
-            }
-        }
-    }
-    (Blizzards(blizzards), Walls(walls))
-}
-
-// We want to display the state. Represent walls with '#', blizzards with
-// '<', '>', '^', or 'v', and empty space with '.'. If multiple blizzards are
-// on the same space, display the count of the number of blizzards on that space.
-// If a single blizzard is at a position, use the directional representation.
-fn display_state(blizzards: &Blizzards, walls: &Walls) {
-    let (max_x, max_y) = walls.0.iter().fold((0, 0), |(max_x, max_y), pos| {
-        (max_x.max(pos.x), max_y.max(pos.y))
-    });
-    for y in 0..=max_y {
-        for x in 0..=max_x {
-            let position = IVec2::new(x, y);
-            let is_wall = walls.0.contains(&position);
-            let has_blizzards = blizzards.0.iter().any(|(_, pos)| *pos == position);
-            if is_wall {
-                print!("#");
-            } else if has_blizzards {
-                let count = blizzards
-                    .0
-                    .iter()
-                    .filter(|(_, pos)| *pos == position)
-                    .count();
-                if count == 1 {
-                    let direction = blizzards
-                        .0
-                        .iter()
-                        .find(|(_, pos)| *pos == position)
-                        .unwrap()
-                        .0;
-                    print!("{}", direction);
-                } else {
-                    print!("{}", count);
-                }
-            } else {
-                print!(".");
-            }
-        }
-        println!();
-    }
-}
-
-struct OpenGraph(DiGraphMap<((i32, i32), u32), ()>);
+impl FromStr for Basin {
+    type Err = String;
 
-impl OpenGraph {
-    fn add_cycle_nodes(&mut self, blizzards: &Blizzards, walls: &Walls, cycle: u32) {
-        let boundaries = walls.boundaries();
-        for x in 0..=boundaries.0 {
-            for y in 0..=boundaries.1 {
-                let position = IVec2::new(x, y);
-                if walls.0.contains(&position) {
-                    continue;
-                }
-                if blizzards.0.iter().any(|(_, pos)| *pos == position) {
-                    continue;
-                }
-                self.0.add_node((position.into(), cycle));
-            }
-        }
-    }
-    fn finalize_edges(&mut self) {
-        let num_cycles = self.0.nodes().map(|(_, cycle)| cycle).max().unwrap();
-        for cycle in 0..num_cycles {
-            let nodes = self
-                .0
-                .nodes()
-                .filter(|(_, c)| *c == cycle)
-                .collect::<Vec<_>>();
-            let next_nodes = self
-                .0
-                .nodes()
-                .filter(|(_, c)| *c == cycle + 1)
-                .collect::<Vec<_>>();
-            for node in nodes {
-                for next_node in &next_nodes {
-                    let node_pos = node.0;
-                    let next_node_pos = next_node.0;
-                    if (node_pos.0 - next_node_pos.0).abs() + (node_pos.1 - next_node_pos.1).abs()
-                        == 1
-                    {
-                        self.0.add_edge(node, *next_node, ());
-                    }
-                }
-                // if this position in the next cycle is open, also add that
-                // edge
-                let same_node_next_cycle = (node.0, cycle + 1);
-                if self.0.contains_node(same_node_next_cycle) {
-                    self.0.add_edge(node, same_node_next_cycle, ());
-                }
-            }
-        }
-        // Repeat for the last cycle, connecting it to cycle 0
-        let nodes = self
-            .0
-            .nodes()
-            .filter(|(_, c)| *c == num_cycles)
-            .collect::<Vec<_>>();
-        let next_nodes = self.0.nodes().filter(|(_, c)| *c == 0).collect::<Vec<_>>();
-        for node in nodes {
-            for next_node in &next_nodes {
-                let node_pos = node.0;
-                let next_node_pos = next_node.0;
-                if (node_pos.0 - next_node_pos.0).abs() + (node_pos.1 - next_node_pos.1).abs() == 1
-                {
-                    self.0.add_edge(node, *next_node, ());
-                }
-            }
-            let same_node_next_cycle = (node.0, 0);
-            if self.0.contains_node(same_node_next_cycle) {
-                self.0.add_edge(node, same_node_next_cycle, ());
-            }
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lines: Vec<&str> = s.lines().collect();
+        if lines.len() < 3 || lines[0].len() < 3 {
+            return Err("The valley needs walls around at least one cell".to_string());
         }
-        // Remove the 'wait' command from the ending nodes, we wouldn't wait,
-        // we're already at the destination
-        for node in self.endings() {
-            self.0.remove_edge(node, (node.0, node.1 + 1));
-            if node.1 == num_cycles {
-                self.0.remove_edge(node, (node.0, 0));
+        let (width, height) = (lines[0].len() - 2, lines.len() - 2);
+        let mut lanes: Vec<(Direction, BitGrid)> = Direction::ALL
+            .iter()
+            .map(|&direction| (direction, BitGrid::new(width, height)))
+            .collect();
+        for (y, line) in lines[1..=height].iter().enumerate() {
+            if line.len() != width + 2 {
+                return Err(format!("Row {} isn't as wide as the valley", y + 1));
             }
-        }
-        for node in self.starts() {
-            self.0.remove_edge(node, (node.0, node.1 + 1));
-            if node.1 == num_cycles {
-                self.0.remove_edge(node, (node.0, 0));
+            for (x, c) in line.chars().enumerate().skip(1).take(width) {
+                let direction = match c {
+                    '.' => continue,
+                    '^' => Direction::Up,
+                    'v' => Direction::Down,
+                    '<' => Direction::Left,
+                    '>' => Direction::Right,
+                    _ => return Err(format!("Unexpected {:?} at {}, {}", c, x, y + 1)),
+                };
+                let (_, lane) = lanes.iter_mut().find(|(d, _)| *d == direction).unwrap();
+                lane.set(x - 1, y, true);
             }
         }
+        let gap = |y: usize| {
+            lines[y]
+                .chars()
+                .position(|c| c == '.')
+                .map(|x| IVec2::new(x as i32, y as i32))
+                .ok_or(format!("No gap in the wall in row {}", y))
+        };
+        // Each lane is back in place after a multiple of the width or height,
+        // but the whole valley can repeat sooner than that
+        let cycle = brent(
+            0,
+            |minute| minute + 1,
+            |&minute| {
+                lanes
+                    .iter()
+                    .map(|(direction, lane)| {
+                        let step = direction.unit_vector() * minute;
+                        lane.rotate(step.x as isize, step.y as isize)
+                    })
+                    .collect::<Vec<_>>()
+            },
+        );
+        Ok(Basin {
+            width: width as i32,
+            height: height as i32,
+            start: gap(0)?,
+            goal: gap(height + 1)?,
+            lanes,
+            cycle,
+        })
     }
-    fn endings(&self) -> Vec<((i32, i32), u32)> {
-        // find all nodes that end in the max y value (second position)
-        let max_y = self
-            .0
-            .nodes()
-            .map(|((_, y), _)| y)
-            .max()
-            .expect("No nodes in graph");
-        self.0.nodes().filter(|((_, y), _)| *y == max_y).collect()
-    }
-    fn starts(&self) -> Vec<((i32, i32), u32)> {
-        // find all nodes that end in the max y value (second position)
-        let min_y = self
-            .0
-            .nodes()
-            .map(|((_, y), _)| y)
-            .min()
-            .expect("No nodes in graph");
-        self.0.nodes().filter(|((_, y), _)| *y == min_y).collect()
+}
+
+impl Basin {
+    fn is_inside(&self, position: IVec2) -> bool {
+        position.cmpge(IVec2::ONE).all() && position.x <= self.width && position.y <= self.height
     }
-    fn find_solution(&self) -> Option<(i32, Vec<((i32, i32), u32)>)> {
-        let mut shortest_path = None;
-        let start = self.0.nodes().find(|node| *node == ((1, 0), 0)).unwrap();
-        let endings = self.endings();
-        let max_cycles = self.0.nodes().map(|(_, cycle)| cycle).max().unwrap();
-        if endings.is_empty() {
-            return None;
-        }
-        // grab any ending
-        let ending = endings[0];
-        // find the manhattan distance between the start position and the ending position
-        let min_dist = (start.0 .0 - ending.0 .0).abs() + (start.0 .1 - ending.0 .1).abs();
-        let reasonable_endings = endings
+
+    // The blizzards on `position` at `minute`
+    fn blizzards(&self, position: IVec2, minute: usize) -> impl Iterator<Item = Direction> + '_ {
+        let inside = self.is_inside(position);
+        let minute = self.cycle.extrapolate(minute) as i32;
+        self.lanes
             .iter()
-            .filter(|ending| ending.1 > min_dist as u32)
-            .collect::<Vec<_>>();
-        println!("original endings: {:?}", &endings.len());
-        println!("reasonable_endings: {:?}", &reasonable_endings.len());
-        for (i, ending) in reasonable_endings.iter().enumerate() {
-            println!("iter: {}", i);
-            let path = astar(
-                &self.0,
-                start,
-                |node| node == **ending,
-                |_| 1,
-                |node| (node.0 .0 - ending.0 .0).abs() + (node.0 .1 - ending.0 .1).abs(),
-            );
-            // if a path is found but it's distance is over the max cycles, continue.
-            // this is because the path is not valid, it's a loop
-            if let Some((distance, _)) = path {
-                if distance > max_cycles as i32 {
-                    continue;
-                }
-            }
-            // if let Some((distance, path)) = path {
-            //     if let Some((shortest_distance, _)) = shortest_path {
-            //         if distance < shortest_distance {
-            //             shortest_path = Some((distance, path));
-            //             println!("shortest path distance: {:?}, iter: {}", distance, i);
-            //         }
-            //     } else {
-            //         shortest_path = Some((distance, path));
-            //     }
-            // }
-            if path.is_some() {
-                return path;
-            }
-        }
-        shortest_path
+            .filter(move |(direction, lane)| {
+                let origin = position - IVec2::ONE - direction.unit_vector() * minute;
+                inside
+                    && lane.get(
+                        origin.x.rem_euclid(self.width) as isize,
+                        origin.y.rem_euclid(self.height) as isize,
+                    )
+            })
+            .map(|(direction, _)| *direction)
     }
-    fn find_solution_part_two(
-        &self,
-    ) -> (
-        Option<(i32, Vec<((i32, i32), u32)>)>,
-        Option<(i32, Vec<((i32, i32), u32)>)>,
-        Option<(i32, Vec<((i32, i32), u32)>)>,
-    ) {
-        let start = self.0.nodes().find(|node| *node == ((1, 0), 0)).unwrap();
-        let endings = self.endings();
-        let max_cycles = self.0.nodes().map(|(_, cycle)| cycle).max().unwrap();
 
-        if endings.is_empty() {
-            return (None, None, None);
-        }
-        // grab any ending
-        let ending = endings[0];
-        // find the manhattan distance between the start position and the ending position
-        let min_dist = (start.0 .0 - ending.0 .0).abs() + (start.0 .1 - ending.0 .1).abs();
-        let reasonable_endings = endings
-            .iter()
-            .filter(|ending| ending.1 > min_dist as u32)
-            .collect::<Vec<_>>();
-        println!("original endings: {:?}", &endings.len());
-        println!("reasonable_endings: {:?}", &reasonable_endings.len());
-        let mut chosen_ending = None;
-        let mut chosen_path = None;
-        for (i, ending) in reasonable_endings.iter().enumerate() {
-            if i != 140 {
-                continue;
-            }
-            println!("iter: {}, stage: 1", i);
-            let path = astar(
-                &self.0,
-                start,
-                |node| node == **ending,
-                |_| 1,
-                |node| (node.0 .0 - ending.0 .0).abs() + (node.0 .1 - ending.0 .1).abs(),
-            );
-            // if let Some((distance, _)) = path {
-            //     if distance > max_cycles as i32 {
-            //         continue;
-            //     }
-            // }
-            if path.is_some() {
-                chosen_ending = Some(**ending);
-                chosen_path = path;
-                println!(
-                    "iter: {}, stage: 1, distance: {:?}, ending: {:?}",
-                    i,
-                    chosen_path.as_ref().unwrap().0,
-                    ending
-                );
-                break;
-            }
-        }
-        let stage_two_start = chosen_ending.unwrap();
-        let stage_two_endings = self.starts();
-        println!("start: {:?}", stage_two_start);
-        let reasonable_stage_two_endings = stage_two_endings
-            .iter()
-            .filter(|ending| ending.1 > min_dist as u32)
-            .collect::<Vec<_>>();
-        let mut chosen_two_ending = None;
-        let mut chosen_two_path = None;
-        for (i, ending) in reasonable_stage_two_endings.iter().enumerate() {
-            println!("iter: {}, stage: 2", i);
-            println!("start: {:?}, ending: {:?}", stage_two_start, ending);
-            let path = astar(
-                &self.0,
-                stage_two_start,
-                |node| node == **ending,
-                |_| 1,
-                |node| (node.0 .0 - ending.0 .0).abs() + (node.0 .1 - ending.0 .1).abs(),
-            );
-            if let Some((distance, _)) = path {
-                if distance > max_cycles as i32 {
-                    continue;
-                }
-            }
-            if path.is_some() {
-                chosen_two_ending = Some(**ending);
-                chosen_two_path = path;
-                println!(
-                    "iter: {}, stage: 2, distance: {:?}, ending: {:?}",
-                    i,
-                    chosen_two_path.as_ref().unwrap().0,
-                    ending
-                );
-                println!("path: {:?}", chosen_two_path);
-                break;
-            }
-        }
-        let stage_three_start = chosen_two_ending.unwrap();
+    fn is_open(&self, position: IVec2, minute: usize) -> bool {
+        position == self.start
+            || position == self.goal
+            || (self.is_inside(position) && self.blizzards(position, minute).next().is_none())
+    }
 
-        let stage_three_endings = self.endings();
-        let reasonable_stage_three_endings = stage_three_endings
-            .iter()
-            .filter(|ending| ending.1 > min_dist as u32)
-            .collect::<Vec<_>>();
-        let mut chosen_three_ending = None;
-        let mut chosen_three_path = None;
-        for (i, ending) in reasonable_stage_three_endings.iter().enumerate() {
-            println!("iter: {}, stage: 3", i);
-            let path = astar(
-                &self.0,
-                stage_three_start,
-                |node| node == **ending,
-                |_| 1,
-                |node| (node.0 .0 - ending.0 .0).abs() + (node.0 .1 - ending.0 .1).abs(),
-            );
-            if let Some((distance, _)) = path {
-                if distance > max_cycles as i32 {
-                    continue;
-                }
-            }
-            if path.is_some() {
-                chosen_three_ending = Some(**ending);
-                chosen_three_path = path;
-                break;
-            }
-        }
-        // return ending from each stage
+    // Every minute the expedition steps in one of the four directions, or
+    // waits where it is
+    fn neighbours(position: IVec2) -> [IVec2; 4] {
+        Direction::ALL.map(|direction| position + direction.unit_vector())
+    }
+
+    // The minute we arrive at each of the waypoints after the first, leaving
+    // from the first one at minute 0 and keeping track of every cell we could
+    // be in minute by minute
+    fn travel(&self, waypoints: &[IVec2]) -> Option<Vec<usize>> {
+        let search = TimedSearch::new(self.cycle.period, Basin::neighbours, |position, minute| {
+            self.is_open(position, minute)
+        });
+        search.journey(waypoints, 0)
+    }
 
-        (chosen_path, chosen_two_path, chosen_three_path)
+    // We want to display the state. Represent walls with '#', blizzards with
+    // '<', '>', '^', or 'v', and empty space with '.'. If multiple blizzards are
+    // on the same space, display the count of the number of blizzards on that space.
+    // If a single blizzard is at a position, use the directional representation.
+    fn display_state(&self, minute: usize) -> String {
+        let rows: Vec<String> = (0..=self.height + 1)
+            .map(|y| {
+                (0..=self.width + 1)
+                    .map(|x| {
+                        let position = IVec2::new(x, y);
+                        if position == self.start || position == self.goal {
+                            return ".".to_string();
+                        }
+                        if !self.is_inside(position) {
+                            return "#".to_string();
+                        }
+                        let blizzards: Vec<Direction> = self.blizzards(position, minute).collect();
+                        match blizzards.len() {
+                            0 => ".".to_string(),
+                            1 => blizzards[0].to_string(),
+                            count => count.to_string(),
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+        rows.join("\n")
     }
 }
 
 pub fn part_one(input: &str) -> Option<u32> {
-    let (mut blizzards, walls) = parse(input);
-    let mut open_graph: OpenGraph = OpenGraph(DiGraphMap::new());
-    let boundaries = walls.boundaries();
-    // open_graph.add_cycle_nodes(&blizzards, &walls, cycle);
-    let num_cycles = lcm(boundaries.0 - 1, boundaries.1 - 1);
-    for cycle in 0..num_cycles {
-        // println!("Cycle {}", cycle);
-        // display_state(&blizzards, &walls);
-        open_graph.add_cycle_nodes(&blizzards, &walls, cycle as u32);
-        blizzards = blizzards.step(&walls);
-    }
-    open_graph.finalize_edges();
-    // println!(
-    //     "{:?}",
-    //     Dot::with_config(&open_graph.0, &[Config::EdgeNoLabel])
-    // );
-    // std::fs::write(
-    //     "graph.dot",
-    //     format!(
-    //         "{:?}",
-    //         Dot::with_config(&open_graph.0, &[Config::EdgeNoLabel])
-    //     ),
-    // )
-    // .unwrap();
-    let solution = open_graph.find_solution();
-    // println!("SOLUTION:\n{:?}", &solution);
-
-    // return the path length from `solution`
-    Some(solution.expect("No Solution").0 as u32)
+    let basin: Basin = input.parse().ok()?;
+    let arrivals = basin.travel(&[basin.start, basin.goal])?;
+    arrivals.last().map(|&minute| minute as u32)
 }
 
 pub fn part_two(input: &str) -> Option<u32> {
-    let (mut blizzards, walls) = parse(input);
-    let mut open_graph: OpenGraph = OpenGraph(DiGraphMap::new());
-    let boundaries = walls.boundaries();
-    // open_graph.add_cycle_nodes(&blizzards, &walls, cycle);
-    // let num_cycles = ((boundaries.0 - 1) * (boundaries.1 - 1)) as u32;
-    let num_cycles_lcm = lcm((boundaries.0 - 1), (boundaries.1 - 1));
-
-    for cycle in 0..num_cycles_lcm {
-        // println!("Cycle {}", cycle);
-        // display_state(&blizzards, &walls);
-        open_graph.add_cycle_nodes(&blizzards, &walls, cycle as u32);
-        blizzards = blizzards.step(&walls);
-    }
-    open_graph.finalize_edges();
-    // println!(
-    //     "{:?}",
-    //     Dot::with_config(&open_graph.0, &[Config::EdgeNoLabel])
-    // );
-    // std::fs::write(
-    //     "graph.dot",
-    //     format!(
-    //         "{:?}",
-    //         Dot::with_config(&open_graph.0, &[Config::EdgeNoLabel])
-    //     ),
-    // )
-    // .unwrap();
-    let solution = open_graph.find_solution_part_two();
-    // println!("SOLUTION:\n{:?}", &solution);
-
-    // return the path length from `solution`
-    let mut total_distance = 0;
-    for path in vec![
-        solution.0.unwrap(),
-        solution.1.unwrap(),
-        solution.2.unwrap(),
-    ]
-    .iter()
-    {
-        total_distance += path.0;
-    }
-    Some(total_distance as u32)
+    let basin: Basin = input.parse().ok()?;
+    let arrivals = basin.travel(&[basin.start, basin.goal, basin.start, basin.goal])?;
+    arrivals.last().map(|&minute| minute as u32)
 }
 
 fn main() {
     let input = &advent_of_code::read_file("inputs", 24);
-    // advent_of_code::solve!(1, part_one, input);
-    // shortest path distance: 286, iter: 140
+    // `cargo solve 24 -- --minute 5` shows the blizzards after 5 minutes
+    let mut args = pico_args::Arguments::from_env();
+    let minute: Option<usize> = args.opt_value_from_str("--minute").unwrap();
+    if let Some(minute) = minute {
+        let basin: Basin = input.parse().unwrap();
+        println!("Minute {}:\n{}\n", minute, basin.display_state(minute));
+    }
+    advent_of_code::solve!(1, part_one, input);
     advent_of_code::solve!(2, part_two, input);
-    // 986 too high
-    // 900 too high
 }
 
 #[cfg(test)]
@@ -584,6 +247,34 @@ mod tests {
     #[test]
     fn test_part_two() {
         let input = advent_of_code::read_file("examples", 24);
-        assert_eq!(part_two(&input), None);
+        assert_eq!(part_two(&input), Some(54));
+    }
+
+    #[test]
+    fn test_blizzards() {
+        let input = advent_of_code::read_file("examples", 24);
+        let basin: Basin = input.parse().unwrap();
+        assert_eq!(basin.display_state(0), input.trim_end());
+        assert_eq!(
+            basin.display_state(1),
+            "#.######\n#.>3.<.#\n#<..<<.#\n#>2.22.#\n#>v..^<#\n######.#"
+        );
+        assert_eq!(basin.display_state(12), input.trim_end());
+        assert_eq!(
+            basin.cycle,
+            Cycle {
+                start: 0,
+                period: 12
+            }
+        );
+        // Nothing moves up or down, so the height doesn't matter
+        let sideways: Basin = "#.###\n#>..#\n#...#\n###.#".parse().unwrap();
+        assert_eq!(sideways.cycle.period, 3);
+        // Any sequence of trips works, here to the goal and back
+        assert_eq!(
+            basin.travel(&[basin.start, basin.goal, basin.start]),
+            Some(vec![18, 41])
+        );
+        assert!("#.#\n#x#\n#.#".parse::<Basin>().is_err());
     }
 }
@@ -1,54 +1,16 @@
-fn snafu_to_int(s: &str) -> i64 {
-    let mut result = 0;
-    let chars: Vec<char> = s.chars().rev().collect();
-    for (i, c) in chars.iter().enumerate() {
-        let base = 5i64.pow(i as u32);
-        match c {
-            '2' => result += 2 * base,
-            '1' => result += 1 * base,
-            '0' => (),
-            '-' => result -= 1 * base,
-            '=' => result -= 2 * base,
-            _ => panic!("Invalid character: {}", c),
-        }
-    }
-    result
-}
-// Definitely needed help on this one
-// https://github.com/ephemient/aoc2022/blob/main/rs/src/day25.rs#L20
-fn int_to_snafu(mut num: i64) -> String {
-    let base = 5;
-    let mut result = String::new();
-
-    while num != 0 {
-        let digit = (num + 2) % base;
-        result.push(match digit {
-            0 => '=',
-            1 => '-',
-            2 => '0',
-            3 => '1',
-            4 => '2',
-            _ => panic!("Invalid digit"),
-        });
-        num = (num + 2) / base;
-    }
-    result.chars().rev().collect()
-}
-
-fn parse_input(input: &str) -> Vec<i64> {
-    input.lines().map(snafu_to_int).collect()
-}
+use advent_of_code::helpers::balanced::BalancedBase;
 
+// Every line is a SNAFU number, and the answer is their sum in SNAFU. The
+// numbers are added up digit by digit, so they never have to fit in an integer.
 pub fn part_one(input: &str) -> Option<String> {
-    let values = parse_input(input);
-    let total = values.iter().sum::<i64>();
-    println!("total: {}", total);
-    let total_snafu = int_to_snafu(total);
-    Some(total_snafu)
-    // None
+    let snafu = BalancedBase::snafu();
+    input
+        .lines()
+        .try_fold("0".to_string(), |total, line| snafu.add(&total, line))
+        .ok()
 }
 
-pub fn part_two(input: &str) -> Option<u32> {
+pub fn part_two(_input: &str) -> Option<u32> {
     None
 }
 
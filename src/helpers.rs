@@ -2,3 +2,18 @@
  * Use this file if you want to extract helpers from your solutions.
  * Example import from this file: `use advent_of_code::helpers::example_fn;`.
  */
+pub mod balanced;
+pub mod bit_grid;
+pub mod cpu;
+pub mod cycle;
+pub mod dot;
+pub mod expr_tree;
+pub mod geometry;
+pub mod marker;
+pub mod ocr;
+pub mod parse;
+pub mod particles;
+pub mod range_set;
+pub mod search;
+pub mod sequence;
+pub mod voxel;
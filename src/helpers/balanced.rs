@@ -0,0 +1,220 @@
+// Balanced numeral systems, like day 25's SNAFU numbers.
+//
+// A balanced base b uses digits that go below zero as well as above it: from
+// -(b - 1) / 2 up to b / 2, so base 5 has -2..=2 and base 4 has -1..=2. Every
+// integer, negative ones included, has exactly one representation without
+// leading zeros. Each digit is written with a character from an alphabet that
+// lists them from the lowest value to the highest.
+//
+// Numbers can be converted to and from `BigInt`, but arithmetic works on the
+// digits directly, column by column with carries, like on paper.
+use num::{BigInt, Integer, Zero};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BalancedBase {
+    alphabet: Vec<char>,
+    // The value of the first digit in the alphabet
+    lowest: i64,
+}
+
+impl BalancedBase {
+    /// A base with one digit for every character of `alphabet`, from the
+    /// lowest value to the highest.
+    pub fn new(alphabet: &str) -> Result<Self, String> {
+        let alphabet: Vec<char> = alphabet.chars().collect();
+        if alphabet.len() < 3 {
+            return Err("A balanced base needs at least 3 digits".to_string());
+        }
+        for (i, c) in alphabet.iter().enumerate() {
+            if alphabet[..i].contains(c) {
+                return Err(format!("Digit {:?} appears more than once", c));
+            }
+        }
+        let lowest = -(alphabet.len() as i64 - 1) / 2;
+        Ok(Self { alphabet, lowest })
+    }
+
+    /// Base 5 with digits `=` (-2), `-` (-1), `0`, `1` and `2`.
+    pub fn snafu() -> Self {
+        Self::new("=-012").unwrap()
+    }
+
+    pub fn base(&self) -> i64 {
+        self.alphabet.len() as i64
+    }
+
+    // The digits of a numeral, least significant first
+    fn digits(&self, numeral: &str) -> Result<Vec<i64>, String> {
+        if numeral.is_empty() {
+            return Err("Empty numeral".to_string());
+        }
+        numeral
+            .chars()
+            .rev()
+            .map(
+                |c| match self.alphabet.iter().position(|&digit| digit == c) {
+                    Some(i) => Ok(self.lowest + i as i64),
+                    None => Err(format!("Invalid digit {:?} in {:?}", c, numeral)),
+                },
+            )
+            .collect()
+    }
+
+    // Turns column sums of any size into digits, carrying into the next
+    // column as needed
+    fn normalize(&self, columns: Vec<i64>) -> Vec<i64> {
+        let mut digits = Vec::with_capacity(columns.len() + 1);
+        let mut carry = 0;
+        let mut columns = columns.into_iter();
+        loop {
+            let sum = match columns.next() {
+                Some(column) => column + carry,
+                None if carry != 0 => carry,
+                None => break,
+            };
+            let digit = (sum - self.lowest).rem_euclid(self.base()) + self.lowest;
+            digits.push(digit);
+            carry = (sum - digit) / self.base();
+        }
+        digits
+    }
+
+    fn render(&self, digits: &[i64]) -> String {
+        let numeral: String = digits
+            .iter()
+            .rev()
+            .skip_while(|&&digit| digit == 0)
+            .map(|&digit| self.alphabet[(digit - self.lowest) as usize])
+            .collect();
+        match numeral.is_empty() {
+            true => self.alphabet[(-self.lowest) as usize].to_string(),
+            false => numeral,
+        }
+    }
+
+    pub fn decode(&self, numeral: &str) -> Result<BigInt, String> {
+        Ok(self
+            .digits(numeral)?
+            .iter()
+            .rev()
+            .fold(BigInt::zero(), |value, &digit| value * self.base() + digit))
+    }
+
+    pub fn encode(&self, value: &BigInt) -> String {
+        let base = BigInt::from(self.base());
+        let mut value = value.clone();
+        let mut digits = vec![];
+        while !value.is_zero() {
+            let digit = (&value - self.lowest).mod_floor(&base) + self.lowest;
+            value = (value - &digit) / &base;
+            digits.push(i64::try_from(digit).unwrap());
+        }
+        self.render(&digits)
+    }
+
+    fn combine(&self, a: &str, b: &str, sign: i64) -> Result<String, String> {
+        let (a, b) = (self.digits(a)?, self.digits(b)?);
+        let columns = (0..a.len().max(b.len()))
+            .map(|i| a.get(i).unwrap_or(&0) + sign * b.get(i).unwrap_or(&0))
+            .collect();
+        Ok(self.render(&self.normalize(columns)))
+    }
+
+    pub fn add(&self, a: &str, b: &str) -> Result<String, String> {
+        self.combine(a, b, 1)
+    }
+
+    pub fn sub(&self, a: &str, b: &str) -> Result<String, String> {
+        self.combine(a, b, -1)
+    }
+
+    pub fn mul(&self, a: &str, b: &str) -> Result<String, String> {
+        let (a, b) = (self.digits(a)?, self.digits(b)?);
+        let mut columns = vec![0; a.len() + b.len()];
+        for (i, x) in a.iter().enumerate() {
+            for (j, y) in b.iter().enumerate() {
+                columns[i + j] += x * y;
+            }
+        }
+        Ok(self.render(&self.normalize(columns)))
+    }
+
+    /// Whether a numeral is below zero, which is the case when its leading
+    /// digit is.
+    pub fn is_negative(&self, numeral: &str) -> Result<bool, String> {
+        let digits = self.digits(numeral)?;
+        let leading = digits.iter().rev().find(|&&digit| digit != 0);
+        Ok(leading.is_some_and(|&digit| digit < 0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Pseudo-random numbers for the property tests
+    fn numbers(seed: i64, count: usize) -> impl Iterator<Item = i64> {
+        (0..count).scan(seed, |random, _| {
+            *random = (*random * 1103515245 + 12345) % (1 << 31);
+            Some(*random % 2_000_001 - 1_000_000)
+        })
+    }
+
+    fn bases() -> Vec<BalancedBase> {
+        (3..=10)
+            .map(|base| BalancedBase::new(&"abcdefghij"[..base]).unwrap())
+            .chain([BalancedBase::snafu(), BalancedBase::new("-0+").unwrap()])
+            .collect()
+    }
+
+    #[test]
+    fn test_snafu() {
+        let snafu = BalancedBase::snafu();
+        for (decimal, numeral) in [
+            (0, "0"),
+            (1, "1"),
+            (3, "1="),
+            (8, "2="),
+            (2022, "1=11-2"),
+            (12345, "1-0---0"),
+            (314159265, "1121-1110-1=0"),
+            (-3, "-2"),
+        ] {
+            assert_eq!(snafu.decode(numeral), Ok(BigInt::from(decimal)));
+            assert_eq!(snafu.encode(&BigInt::from(decimal)), numeral);
+        }
+        assert_eq!(snafu.add("1=", "2="), Ok("21".to_string()));
+        assert_eq!(snafu.is_negative("00"), Ok(false));
+        assert!(snafu.decode("13").is_err());
+        assert!(BalancedBase::new("ab").is_err());
+        assert!(BalancedBase::new("aba").is_err());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let big: BigInt = BigInt::from(i64::MAX) * BigInt::from(i64::MAX) + 17;
+        for base in bases() {
+            for n in numbers(base.base(), 200).chain([0, 1, -1]) {
+                let value = BigInt::from(n);
+                let numeral = base.encode(&value);
+                assert_eq!(base.decode(&numeral), Ok(value), "{}", numeral);
+            }
+            assert_eq!(base.decode(&base.encode(&-&big)), Ok(-&big));
+        }
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        for base in bases() {
+            let pairs = numbers(base.base(), 100).zip(numbers(-base.base(), 100));
+            for (a, b) in pairs {
+                let (x, y) = (base.encode(&a.into()), base.encode(&b.into()));
+                let decode = |numeral: Result<String, String>| base.decode(&numeral.unwrap());
+                assert_eq!(decode(base.add(&x, &y)), Ok(BigInt::from(a + b)));
+                assert_eq!(decode(base.sub(&x, &y)), Ok(BigInt::from(a - b)));
+                assert_eq!(decode(base.mul(&x, &y)), Ok(BigInt::from(a * b)));
+                assert_eq!(base.is_negative(&x), Ok(a < 0));
+            }
+        }
+    }
+}
@@ -0,0 +1,276 @@
+// A fixed-size grid of bits, for cellular simulations that update every cell
+// at once.
+//
+// Each row is a run of u64 words, with x = 0 in the lowest bit of the first
+// word. Asking "which cells have a neighbour to the north" is then a single
+// shifted copy of the grid, and combining such masks with `&`, `|` and `!`
+// handles 64 cells per instruction. Anything shifted past an edge is dropped
+// unless the grid is rotated instead.
+use std::ops::{BitAnd, BitOr, BitXor, Not};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BitGrid {
+    width: usize,
+    height: usize,
+    // Words per row
+    stride: usize,
+    bits: Vec<u64>,
+}
+
+impl BitGrid {
+    pub fn new(width: usize, height: usize) -> Self {
+        let stride = width.div_ceil(64);
+        Self {
+            width,
+            height,
+            stride,
+            bits: vec![0; stride * height],
+        }
+    }
+
+    /// A grid of the given size with every cell set.
+    pub fn full(width: usize, height: usize) -> Self {
+        !&Self::new(width, height)
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    fn row(&self, y: usize) -> &[u64] {
+        &self.bits[y * self.stride..(y + 1) * self.stride]
+    }
+
+    /// Cells outside the grid are never set.
+    pub fn get(&self, x: isize, y: isize) -> bool {
+        if x < 0 || y < 0 || x as usize >= self.width || y as usize >= self.height {
+            return false;
+        }
+        let (x, y) = (x as usize, y as usize);
+        self.row(y)[x / 64] >> (x % 64) & 1 == 1
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, value: bool) {
+        assert!(
+            x < self.width && y < self.height,
+            "({}, {}) is outside the grid",
+            x,
+            y
+        );
+        let word = &mut self.bits[y * self.stride + x / 64];
+        if value {
+            *word |= 1 << (x % 64);
+        } else {
+            *word &= !(1 << (x % 64));
+        }
+    }
+
+    pub fn count_ones(&self) -> usize {
+        self.bits
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bits.iter().all(|word| *word == 0)
+    }
+
+    /// The coordinates of every set cell, row by row.
+    pub fn iter(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        (0..self.height).flat_map(move |y| {
+            (0..self.width)
+                .filter(move |&x| self.row(y)[x / 64] >> (x % 64) & 1 == 1)
+                .map(move |x| (x, y))
+        })
+    }
+
+    // Clears the bits past the end of every row
+    fn trim(&mut self) {
+        let used = self.width % 64;
+        if used == 0 {
+            return;
+        }
+        for y in 0..self.height {
+            self.bits[y * self.stride + self.stride - 1] &= (1 << used) - 1;
+        }
+    }
+
+    /// Moves every cell by `(dx, dy)`. Cells that end up outside are lost and
+    /// the ones that come in are empty.
+    pub fn shift(&self, dx: isize, dy: isize) -> BitGrid {
+        let mut shifted = BitGrid::new(self.width, self.height);
+        let (words, bits) = (dx.div_euclid(64), dx.rem_euclid(64) as u32);
+        let stride = self.stride as isize;
+        for y in 0..self.height as isize {
+            let from = y - dy;
+            if from < 0 || from >= self.height as isize {
+                continue;
+            }
+            let source = self.row(from as usize);
+            let word = |i: isize| {
+                if (0..stride).contains(&i) {
+                    source[i as usize]
+                } else {
+                    0
+                }
+            };
+            for i in 0..stride {
+                // Word i gets its high bits from one source word and its low
+                // bits from the one before that
+                let high = word(i - words);
+                shifted.bits[(y * stride + i) as usize] = match bits {
+                    0 => high,
+                    _ => high << bits | word(i - words - 1) >> (64 - bits),
+                };
+            }
+        }
+        shifted.trim();
+        shifted
+    }
+
+    /// The cells that have a set cell at offset `(dx, dy)` from them, so
+    /// `neighbour(0, -1)` marks everything with something directly above it.
+    pub fn neighbour(&self, dx: isize, dy: isize) -> BitGrid {
+        self.shift(-dx, -dy)
+    }
+
+    /// Like `shift`, but whatever leaves one edge comes back in on the other.
+    pub fn rotate(&self, dx: isize, dy: isize) -> BitGrid {
+        let (width, height) = (self.width as isize, self.height as isize);
+        let mut rotated = self.clone();
+        let dx = dx.rem_euclid(width.max(1));
+        if dx != 0 {
+            rotated = &rotated.shift(dx, 0) | &rotated.shift(dx - width, 0);
+        }
+        let dy = dy.rem_euclid(height.max(1));
+        if dy != 0 {
+            rotated = &rotated.shift(0, dy) | &rotated.shift(0, dy - height);
+        }
+        rotated
+    }
+
+    /// The same cells in a grid with `margin` empty cells added on every side.
+    pub fn pad(&self, margin: usize) -> BitGrid {
+        let mut padded = BitGrid::new(self.width + 2 * margin, self.height + 2 * margin);
+        for (x, y) in self.iter() {
+            padded.set(x + margin, y + margin, true);
+        }
+        padded
+    }
+
+    fn combine(&self, other: &BitGrid, op: impl Fn(u64, u64) -> u64) -> BitGrid {
+        assert_eq!(
+            (self.width, self.height),
+            (other.width, other.height),
+            "grids have different sizes"
+        );
+        BitGrid {
+            bits: self
+                .bits
+                .iter()
+                .zip(&other.bits)
+                .map(|(a, b)| op(*a, *b))
+                .collect(),
+            ..self.clone()
+        }
+    }
+
+    /// The cells set here but not in `other`.
+    pub fn and_not(&self, other: &BitGrid) -> BitGrid {
+        self.combine(other, |a, b| a & !b)
+    }
+}
+
+impl BitAnd for &BitGrid {
+    type Output = BitGrid;
+
+    fn bitand(self, other: &BitGrid) -> BitGrid {
+        self.combine(other, |a, b| a & b)
+    }
+}
+
+impl BitOr for &BitGrid {
+    type Output = BitGrid;
+
+    fn bitor(self, other: &BitGrid) -> BitGrid {
+        self.combine(other, |a, b| a | b)
+    }
+}
+
+impl BitXor for &BitGrid {
+    type Output = BitGrid;
+
+    fn bitxor(self, other: &BitGrid) -> BitGrid {
+        self.combine(other, |a, b| a ^ b)
+    }
+}
+
+impl Not for &BitGrid {
+    type Output = BitGrid;
+
+    fn not(self) -> BitGrid {
+        let mut inverted = BitGrid {
+            bits: self.bits.iter().map(|word| !word).collect(),
+            ..self.clone()
+        };
+        inverted.trim();
+        inverted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid(width: usize, height: usize, cells: &[(usize, usize)]) -> BitGrid {
+        let mut grid = BitGrid::new(width, height);
+        for &(x, y) in cells {
+            grid.set(x, y, true);
+        }
+        grid
+    }
+
+    #[test]
+    fn test_shift_across_words() {
+        let grid = grid(130, 3, &[(0, 0), (63, 1), (64, 1), (129, 2)]);
+        let shifted = grid.shift(1, 0);
+        assert_eq!(
+            shifted.iter().collect::<Vec<_>>(),
+            vec![(1, 0), (64, 1), (65, 1)]
+        );
+        let shifted = grid.shift(-64, 1);
+        assert_eq!(shifted.iter().collect::<Vec<_>>(), vec![(0, 2)]);
+        assert_eq!(
+            grid.neighbour(0, -1).iter().collect::<Vec<_>>(),
+            vec![(0, 1), (63, 2), (64, 2)]
+        );
+    }
+
+    #[test]
+    fn test_rotate() {
+        let grid = grid(70, 2, &[(0, 0), (69, 1)]);
+        assert_eq!(
+            grid.rotate(-1, 1).iter().collect::<Vec<_>>(),
+            vec![(68, 0), (69, 1)]
+        );
+        assert_eq!(grid.rotate(70, -4), grid);
+    }
+
+    #[test]
+    fn test_ops() {
+        let a = grid(5, 1, &[(0, 0), (1, 0)]);
+        let b = grid(5, 1, &[(1, 0), (2, 0)]);
+        assert_eq!((&a & &b).count_ones(), 1);
+        assert_eq!((&a | &b).count_ones(), 3);
+        assert_eq!((&a ^ &b).count_ones(), 2);
+        assert_eq!((!&a).count_ones(), 3);
+        assert_eq!(a.and_not(&b).iter().collect::<Vec<_>>(), vec![(0, 0)]);
+        assert_eq!(BitGrid::full(65, 2).count_ones(), 130);
+        assert_eq!(a.pad(1).iter().collect::<Vec<_>>(), vec![(1, 1), (2, 1)]);
+    }
+}
@@ -0,0 +1,251 @@
+// A tiny virtual machine for the assembly-style puzzles.
+//
+// The instruction set is data: every instruction is declared with a name, the
+// number of arguments it takes, how many cycles it needs, and what it does to
+// the registers once those cycles are done. A puzzle like day 10 is then just
+// a register type, an `InstructionSet` and a hook that watches the cycles go by.
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+pub type Execute<R> = fn(&mut R, &[i64]);
+
+struct Definition<R> {
+    arity: usize,
+    cycles: u32,
+    execute: Execute<R>,
+}
+
+pub struct InstructionSet<R> {
+    definitions: HashMap<String, Definition<R>>,
+}
+
+impl<R> Default for InstructionSet<R> {
+    fn default() -> Self {
+        Self {
+            definitions: HashMap::new(),
+        }
+    }
+}
+
+impl<R> InstructionSet<R> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Declares an instruction taking `arity` integer arguments. It occupies
+    /// the CPU for `cycles` cycles and applies `execute` at the end of the last one.
+    pub fn define(mut self, name: &str, arity: usize, cycles: u32, execute: Execute<R>) -> Self {
+        assert!(cycles > 0, "instructions take at least one cycle");
+        self.definitions.insert(
+            name.to_string(),
+            Definition {
+                arity,
+                cycles,
+                execute,
+            },
+        );
+        self
+    }
+
+    pub fn parse(&self, line: &str) -> Result<Instruction, String> {
+        let mut parts = line.split_whitespace();
+        let name = parts.next().ok_or("Empty instruction")?;
+        let definition = self
+            .definitions
+            .get(name)
+            .ok_or_else(|| format!("Unknown instruction: {}", name))?;
+        let args = parts
+            .map(|arg| arg.parse::<i64>().map_err(|e| format!("{}: {}", arg, e)))
+            .collect::<Result<Vec<i64>, String>>()?;
+        if args.len() != definition.arity {
+            return Err(format!(
+                "{} takes {} arguments, got {}",
+                name,
+                definition.arity,
+                args.len()
+            ));
+        }
+        Ok(Instruction {
+            name: name.to_string(),
+            args,
+        })
+    }
+
+    /// Parses one instruction per line.
+    pub fn parse_program(&self, input: &str) -> Result<Vec<Instruction>, String> {
+        input
+            .lines()
+            .enumerate()
+            .map(|(i, line)| {
+                self.parse(line)
+                    .map_err(|e| format!("line {}: {}", i + 1, e))
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Instruction {
+    pub name: String,
+    pub args: Vec<i64>,
+}
+
+impl std::fmt::Display for Instruction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name)?;
+        for arg in &self.args {
+            write!(f, " {}", arg)?;
+        }
+        Ok(())
+    }
+}
+
+/// Puzzles care about the registers either while a cycle is running (before
+/// any instruction finishing in it has taken effect) or once it has finished.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    During,
+    After,
+}
+
+/// What a hook gets to see: the 1-based cycle number, the phase, and the
+/// registers at that point.
+pub struct Tick<'a, R> {
+    pub cycle: u64,
+    pub phase: Phase,
+    pub registers: &'a R,
+}
+
+pub struct Cpu<'a, R> {
+    instructions: &'a InstructionSet<R>,
+    pub registers: R,
+    pub cycle: u64,
+    trace: Option<Box<dyn Write + 'a>>,
+}
+
+impl<'a, R: Debug> Cpu<'a, R> {
+    pub fn new(instructions: &'a InstructionSet<R>, registers: R) -> Self {
+        Self {
+            instructions,
+            registers,
+            cycle: 0,
+            trace: None,
+        }
+    }
+
+    /// Writes one line per cycle: the cycle, the instruction being executed,
+    /// and the registers during and after it.
+    pub fn with_trace(mut self, writer: impl Write + 'a) -> Self {
+        self.trace = Some(Box::new(writer));
+        self
+    }
+
+    pub fn trace_to_file(self, path: impl AsRef<Path>) -> Result<Self, String> {
+        let file = File::create(path).map_err(|e| e.to_string())?;
+        Ok(self.with_trace(BufWriter::new(file)))
+    }
+
+    /// Runs `program` to the end, calling `hook` twice per cycle.
+    pub fn run<F>(&mut self, program: &[Instruction], mut hook: F) -> Result<(), String>
+    where
+        F: FnMut(&Tick<R>),
+    {
+        for instruction in program {
+            let definition = self
+                .instructions
+                .definitions
+                .get(&instruction.name)
+                .ok_or_else(|| format!("Unknown instruction: {}", instruction.name))?;
+            for remaining in (0..definition.cycles).rev() {
+                self.cycle += 1;
+                hook(&Tick {
+                    cycle: self.cycle,
+                    phase: Phase::During,
+                    registers: &self.registers,
+                });
+                let during = self
+                    .trace
+                    .is_some()
+                    .then(|| format!("{:?}", self.registers));
+                if remaining == 0 {
+                    (definition.execute)(&mut self.registers, &instruction.args);
+                }
+                if let (Some(trace), Some(during)) = (self.trace.as_mut(), during) {
+                    writeln!(
+                        trace,
+                        "{}\t{}\t{}\t{:?}",
+                        self.cycle, instruction, during, self.registers
+                    )
+                    .map_err(|e| e.to_string())?;
+                }
+                hook(&Tick {
+                    cycle: self.cycle,
+                    phase: Phase::After,
+                    registers: &self.registers,
+                });
+            }
+        }
+        if let Some(trace) = self.trace.as_mut() {
+            trace.flush().map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn instruction_set() -> InstructionSet<i64> {
+        InstructionSet::new()
+            .define("noop", 0, 1, |_, _| {})
+            .define("addx", 1, 2, |x, args| *x += args[0])
+    }
+
+    #[test]
+    fn test_run_with_hooks() {
+        let set = instruction_set();
+        let program = set.parse_program("noop\naddx 3\naddx -5").unwrap();
+        let mut ticks = vec![];
+        let mut cpu = Cpu::new(&set, 1);
+        cpu.run(&program, |tick| {
+            ticks.push((tick.cycle, tick.phase, *tick.registers))
+        })
+        .unwrap();
+        assert_eq!(cpu.cycle, 5);
+        assert_eq!(cpu.registers, -1);
+        assert_eq!(ticks[4], (3, Phase::During, 1));
+        assert_eq!(ticks[5], (3, Phase::After, 4));
+        assert_eq!(ticks[9], (5, Phase::After, -1));
+    }
+
+    #[test]
+    fn test_trace() {
+        let set = instruction_set();
+        let program = set.parse_program("noop\naddx 3").unwrap();
+        let mut trace = vec![];
+        Cpu::new(&set, 1)
+            .with_trace(&mut trace)
+            .run(&program, |_| {})
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(trace).unwrap(),
+            "1\tnoop\t1\t1\n2\taddx 3\t1\t1\n3\taddx 3\t1\t4\n"
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let set = instruction_set();
+        assert!(set.parse("jmp 2").is_err());
+        assert!(set.parse("addx").is_err());
+        assert!(set.parse("addx x").is_err());
+        assert_eq!(
+            set.parse_program("noop\nnoop 1").unwrap_err(),
+            "line 2: noop takes 0 arguments, got 1"
+        );
+    }
+}
@@ -0,0 +1,174 @@
+// Cycle detection for simulations that eventually repeat.
+//
+// Lots of puzzles ask for the state after an absurd number of steps, but the
+// simulation settles into a loop long before that. Once we know where the loop
+// starts and how long it is, step `n` can be mapped back onto a step we've
+// already simulated.
+//
+// All detectors take the initial state, a transition closure and a key
+// closure. Only keys are compared, so a state can carry data that doesn't
+// matter for repetition (a round counter, an accumulated score, ...).
+use std::collections::HashMap;
+use std::hash::Hash;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cycle {
+    /// Index of the first state that is part of the cycle
+    pub start: usize,
+    /// Number of steps until a state in the cycle repeats
+    pub period: usize,
+}
+
+impl Cycle {
+    /// Maps step `n` onto the equivalent step before `start + period`.
+    pub fn extrapolate(&self, n: usize) -> usize {
+        if n < self.start {
+            n
+        } else {
+            self.start + (n - self.start) % self.period
+        }
+    }
+}
+
+/// Floyd's tortoise and hare. Keeps two states around and needs no hashing.
+pub fn floyd<S, K, F, G>(initial: S, mut step: F, key: G) -> Cycle
+where
+    K: PartialEq,
+    F: FnMut(&S) -> S,
+    G: Fn(&S) -> K,
+{
+    let mut tortoise = step(&initial);
+    let mut hare = step(&tortoise);
+    while key(&tortoise) != key(&hare) {
+        tortoise = step(&tortoise);
+        hare = step(&hare);
+        hare = step(&hare);
+    }
+
+    // The distance from the start to the meeting point is a multiple of the
+    // period, so walking both from there in lockstep meets at the cycle start
+    let mut start = 0;
+    tortoise = initial;
+    while key(&tortoise) != key(&hare) {
+        tortoise = step(&tortoise);
+        hare = step(&hare);
+        start += 1;
+    }
+
+    let mut period = 1;
+    hare = step(&tortoise);
+    while key(&tortoise) != key(&hare) {
+        hare = step(&hare);
+        period += 1;
+    }
+    Cycle { start, period }
+}
+
+/// Brent's algorithm. Same memory as Floyd's, but fewer calls to `step`.
+pub fn brent<S, K, F, G>(initial: S, mut step: F, key: G) -> Cycle
+where
+    S: Clone,
+    K: PartialEq,
+    F: FnMut(&S) -> S,
+    G: Fn(&S) -> K,
+{
+    let mut power = 1;
+    let mut period = 1;
+    let mut tortoise = initial.clone();
+    let mut hare = step(&initial);
+    while key(&tortoise) != key(&hare) {
+        if power == period {
+            tortoise = hare.clone();
+            power *= 2;
+            period = 0;
+        }
+        hare = step(&hare);
+        period += 1;
+    }
+
+    let mut start = 0;
+    tortoise = initial.clone();
+    hare = initial;
+    for _ in 0..period {
+        hare = step(&hare);
+    }
+    while key(&tortoise) != key(&hare) {
+        tortoise = step(&tortoise);
+        hare = step(&hare);
+        start += 1;
+    }
+    Cycle { start, period }
+}
+
+/// Remembers the key of every state it has seen, so `step` runs exactly
+/// `start + period` times. Use this when stepping is expensive and the keys
+/// are small.
+pub fn memoized<S, K, F, G>(initial: S, mut step: F, key: G) -> Cycle
+where
+    K: Hash + Eq,
+    F: FnMut(&S) -> S,
+    G: Fn(&S) -> K,
+{
+    let mut seen: HashMap<K, usize> = HashMap::new();
+    let mut state = initial;
+    let mut index = 0;
+    loop {
+        if let Some(start) = seen.insert(key(&state), index) {
+            return Cycle {
+                start,
+                period: index - start,
+            };
+        }
+        state = step(&state);
+        index += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 0, 1, 2, 3, 4, 5, 2, 3, 4, 5, ...
+    fn next(x: &u32) -> u32 {
+        if *x < 5 {
+            x + 1
+        } else {
+            2
+        }
+    }
+
+    #[test]
+    fn test_detectors_agree() {
+        let expected = Cycle {
+            start: 2,
+            period: 4,
+        };
+        assert_eq!(floyd(0, next, |x| *x), expected);
+        assert_eq!(brent(0, next, |x| *x), expected);
+        assert_eq!(memoized(0, next, |x| *x), expected);
+    }
+
+    #[test]
+    fn test_key_ignores_extra_state() {
+        // the step counter never repeats, but the key does
+        let step = |(x, count): &(u32, u32)| (next(x), count + 1);
+        let expected = Cycle {
+            start: 2,
+            period: 4,
+        };
+        assert_eq!(brent((0, 0), step, |(x, _)| *x), expected);
+        assert_eq!(memoized((0, 0), step, |(x, _)| *x), expected);
+    }
+
+    #[test]
+    fn test_extrapolate() {
+        let cycle = Cycle {
+            start: 2,
+            period: 4,
+        };
+        assert_eq!(cycle.extrapolate(1), 1);
+        assert_eq!(cycle.extrapolate(5), 5);
+        assert_eq!(cycle.extrapolate(6), 2);
+        assert_eq!(cycle.extrapolate(1_000_000_003), 3);
+    }
+}
@@ -0,0 +1,94 @@
+// Writes graphs in Graphviz's DOT language, for looking at puzzle structures
+// with `dot -Tsvg`. Nodes and edges are printed in the order they were added.
+use std::fmt;
+
+type Attributes = Vec<(String, String)>;
+
+/// A directed graph whose nodes are identified by name.
+#[derive(Debug, Clone, Default)]
+pub struct Digraph {
+    name: String,
+    nodes: Vec<(String, Attributes)>,
+    edges: Vec<(String, String, Attributes)>,
+}
+
+// DOT identifiers are always quoted, so only quotes and backslashes need escaping
+fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn attributes(attrs: &[(String, String)]) -> String {
+    if attrs.is_empty() {
+        return String::new();
+    }
+    let attrs: Vec<String> = attrs
+        .iter()
+        .map(|(key, value)| format!("{}={}", key, quote(value)))
+        .collect();
+    format!(" [{}]", attrs.join(", "))
+}
+
+fn owned(attrs: &[(&str, &str)]) -> Attributes {
+    attrs
+        .iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
+
+impl Digraph {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Adds a node with attributes such as `("label", "x")` or `("fillcolor", "red")`.
+    pub fn node(&mut self, id: &str, attrs: &[(&str, &str)]) -> &mut Self {
+        self.nodes.push((id.to_string(), owned(attrs)));
+        self
+    }
+
+    pub fn edge(&mut self, from: &str, to: &str, attrs: &[(&str, &str)]) -> &mut Self {
+        self.edges
+            .push((from.to_string(), to.to_string(), owned(attrs)));
+        self
+    }
+}
+
+impl fmt::Display for Digraph {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "digraph {} {{", quote(&self.name))?;
+        for (id, attrs) in &self.nodes {
+            writeln!(f, "    {}{};", quote(id), attributes(attrs))?;
+        }
+        for (from, to, attrs) in &self.edges {
+            writeln!(
+                f,
+                "    {} -> {}{};",
+                quote(from),
+                quote(to),
+                attributes(attrs)
+            )?;
+        }
+        writeln!(f, "}}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        let mut graph = Digraph::new("g");
+        graph
+            .node("a", &[("label", "say \"hi\"")])
+            .node("b", &[])
+            .edge("a", "b", &[("color", "red")]);
+        assert_eq!(
+            graph.to_string(),
+            "digraph \"g\" {\n    \"a\" [label=\"say \\\"hi\\\"\"];\n    \"b\";\n    \"a\" -> \"b\" [color=\"red\"];\n}\n"
+        );
+    }
+}
@@ -0,0 +1,159 @@
+// Named expression trees, like day 21's monkeys: every node is either a leaf
+// (usually a number) or an operator applied to two other nodes, which it refers
+// to by name. One node can be singled out as the variable, which is written as
+// its name instead of its value and highlighted in graphs along with every
+// node that depends on it.
+use std::collections::{HashMap, HashSet};
+
+use super::dot::Digraph;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Node {
+    Leaf(String),
+    /// The names of the left and right operands, with the operator between them.
+    Operation(String, char, String),
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ExprTree {
+    nodes: HashMap<String, Node>,
+}
+
+impl FromIterator<(String, Node)> for ExprTree {
+    fn from_iter<I: IntoIterator<Item = (String, Node)>>(iter: I) -> Self {
+        Self {
+            nodes: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl ExprTree {
+    pub fn get(&self, name: &str) -> Option<&Node> {
+        self.nodes.get(name)
+    }
+
+    /// The nodes whose value changes with `variable`, `variable` included.
+    pub fn dependents(&self, variable: &str) -> HashSet<String> {
+        fn visit<'a>(
+            tree: &'a ExprTree,
+            name: &'a str,
+            variable: &str,
+            seen: &mut HashMap<&'a str, bool>,
+        ) -> bool {
+            if let Some(dependent) = seen.get(name) {
+                return *dependent;
+            }
+            let dependent = name == variable
+                || match tree.get(name) {
+                    Some(Node::Operation(left, _, right)) => {
+                        // visit both sides so every node ends up in `seen`
+                        visit(tree, left, variable, seen) | visit(tree, right, variable, seen)
+                    }
+                    _ => false,
+                };
+            seen.insert(name, dependent);
+            dependent
+        }
+        let mut seen = HashMap::new();
+        for name in self.nodes.keys() {
+            visit(self, name, variable, &mut seen);
+        }
+        seen.into_iter()
+            .filter(|(_, dependent)| *dependent)
+            .map(|(name, _)| name.to_string())
+            .collect()
+    }
+
+    /// The expression below `name` written out in full, with every operation
+    /// in parentheses and `variable` left as it is.
+    pub fn infix(&self, name: &str, variable: &str) -> Result<String, String> {
+        if name == variable {
+            return Ok(name.to_string());
+        }
+        match self.get(name) {
+            None => Err(format!("Unknown node: {}", name)),
+            Some(Node::Leaf(value)) => Ok(value.clone()),
+            Some(Node::Operation(left, operator, right)) => Ok(format!(
+                "({} {} {})",
+                self.infix(left, variable)?,
+                operator,
+                self.infix(right, variable)?
+            )),
+        }
+    }
+
+    /// Every node as a graph node pointing at its operands, in order of name.
+    /// `variable` is red, the nodes that depend on it orange and the rest grey.
+    pub fn to_dot(&self, graph: &str, variable: &str) -> Digraph {
+        let dependent = self.dependents(variable);
+        let mut names: Vec<&String> = self.nodes.keys().collect();
+        names.sort_unstable();
+        let mut digraph = Digraph::new(graph);
+        for name in names {
+            let (label, operands) = match &self.nodes[name] {
+                Node::Leaf(value) => (format!("{}: {}", name, value), vec![]),
+                Node::Operation(left, operator, right) => (
+                    format!("{}: {} {} {}", name, left, operator, right),
+                    vec![left, right],
+                ),
+            };
+            let color = match name {
+                _ if name == variable => "red",
+                _ if dependent.contains(name) => "orange",
+                _ => "lightgrey",
+            };
+            digraph.node(
+                name,
+                &[("label", &label), ("style", "filled"), ("fillcolor", color)],
+            );
+            for operand in operands {
+                digraph.edge(name, operand, &[]);
+            }
+        }
+        digraph
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // sum = x + (two * three)
+    fn tree() -> ExprTree {
+        let operation = |left: &str, operator, right: &str| {
+            Node::Operation(left.to_string(), operator, right.to_string())
+        };
+        [
+            ("sum", operation("x", '+', "product")),
+            ("product", operation("two", '*', "three")),
+            ("x", Node::Leaf("1".to_string())),
+            ("two", Node::Leaf("2".to_string())),
+            ("three", Node::Leaf("3".to_string())),
+        ]
+        .into_iter()
+        .map(|(name, node)| (name.to_string(), node))
+        .collect()
+    }
+
+    #[test]
+    fn test_infix() {
+        let tree = tree();
+        assert_eq!(tree.infix("sum", "x"), Ok("(x + (2 * 3))".to_string()));
+        assert_eq!(tree.infix("sum", "two"), Ok("(1 + (two * 3))".to_string()));
+        assert!(tree.infix("missing", "x").is_err());
+        let mut dependents: Vec<String> = tree.dependents("two").into_iter().collect();
+        dependents.sort_unstable();
+        assert_eq!(dependents, vec!["product", "sum", "two"]);
+    }
+
+    #[test]
+    fn test_dot() {
+        let dot = tree().to_dot("tree", "x").to_string();
+        assert!(dot.starts_with("digraph \"tree\" {\n    \"product\" [label=\"product: two * three\", style=\"filled\", fillcolor=\"lightgrey\"];\n"));
+        assert!(dot.contains(
+            "\"sum\" [label=\"sum: x + product\", style=\"filled\", fillcolor=\"orange\"];"
+        ));
+        assert!(dot.contains("\"x\" [label=\"x: 1\", style=\"filled\", fillcolor=\"red\"];"));
+        assert!(dot.contains("\"sum\" -> \"product\";"));
+    }
+}
@@ -0,0 +1,142 @@
+// Manhattan (taxicab) geometry on the integer grid.
+//
+// The set of cells within a Manhattan distance `radius` of a center is a
+// diamond. Rotating the grid by 45° with `u = x + y`, `v = x - y` turns that
+// diamond into an axis-aligned square, and its edges into the diagonals
+// `x + y = c` and `x - y = c`. Reasoning about those diagonals is much cheaper
+// than enumerating every cell on the edge of a large diamond.
+use std::collections::HashSet;
+
+/// Rotates a point 45° into `(x + y, x - y)` coordinates.
+pub fn rotate((x, y): (i64, i64)) -> (i64, i64) {
+    (x + y, x - y)
+}
+
+/// Inverse of `rotate`. Only rotated points where `u` and `v` have the same
+/// parity map back onto the integer grid.
+pub fn unrotate((u, v): (i64, i64)) -> Option<(i64, i64)> {
+    if (u + v).rem_euclid(2) != 0 {
+        return None;
+    }
+    Some(((u + v) / 2, (u - v) / 2))
+}
+
+pub fn manhattan((x1, y1): (i64, i64), (x2, y2): (i64, i64)) -> i64 {
+    (x1 - x2).abs() + (y1 - y2).abs()
+}
+
+/// A diagonal line through the grid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Diagonal {
+    /// `x + y = c`, going down to the left
+    Descending(i64),
+    /// `x - y = c`, going down to the right
+    Ascending(i64),
+}
+
+impl Diagonal {
+    /// The grid cell where two diagonals cross. Parallel lines, or lines that
+    /// cross between cells, have no intersection.
+    pub fn intersect(&self, other: &Diagonal) -> Option<(i64, i64)> {
+        match (self, other) {
+            (Diagonal::Descending(u), Diagonal::Ascending(v))
+            | (Diagonal::Ascending(v), Diagonal::Descending(u)) => unrotate((*u, *v)),
+            _ => None,
+        }
+    }
+}
+
+/// All cells within Manhattan distance `radius` of `center`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Diamond {
+    pub center: (i64, i64),
+    pub radius: i64,
+}
+
+impl Diamond {
+    pub fn new(center: (i64, i64), radius: i64) -> Self {
+        Self { center, radius }
+    }
+
+    pub fn contains(&self, point: (i64, i64)) -> bool {
+        manhattan(self.center, point) <= self.radius
+    }
+
+    /// The closed range of `x` values the diamond covers in row `y`.
+    pub fn row(&self, y: i64) -> Option<(i64, i64)> {
+        let half_width = self.radius - (self.center.1 - y).abs();
+        if half_width < 0 {
+            return None;
+        }
+        Some((self.center.0 - half_width, self.center.0 + half_width))
+    }
+
+    /// The four diagonals the edges of the diamond lie on, after growing it by
+    /// `margin`. A margin of 1 gives the lines just outside the diamond.
+    pub fn boundary(&self, margin: i64) -> [Diagonal; 4] {
+        let (u, v) = rotate(self.center);
+        let reach = self.radius + margin;
+        [
+            Diagonal::Descending(u - reach),
+            Diagonal::Descending(u + reach),
+            Diagonal::Ascending(v - reach),
+            Diagonal::Ascending(v + reach),
+        ]
+    }
+}
+
+/// Every cell where the diagonals bounding any of the `diamonds` (grown by
+/// `margin`) cross each other. If a single cell is left uncovered by a set of
+/// diamonds, it's wedged between their edges, so it's one of these
+/// intersections with `margin = 1`.
+pub fn boundary_intersections(diamonds: &[Diamond], margin: i64) -> HashSet<(i64, i64)> {
+    let mut descending = HashSet::new();
+    let mut ascending = HashSet::new();
+    for line in diamonds.iter().flat_map(|d| d.boundary(margin)) {
+        match line {
+            Diagonal::Descending(_) => descending.insert(line),
+            Diagonal::Ascending(_) => ascending.insert(line),
+        };
+    }
+    descending
+        .iter()
+        .flat_map(|d| ascending.iter().filter_map(move |a| d.intersect(a)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rotate_round_trip() {
+        for point in [(0, 0), (3, -7), (-12, 5)] {
+            assert_eq!(unrotate(rotate(point)), Some(point));
+        }
+        assert_eq!(unrotate((1, 0)), None);
+    }
+
+    #[test]
+    fn test_diamond() {
+        let diamond = Diamond::new((8, 7), 9);
+        assert!(diamond.contains((8, -2)));
+        assert!(diamond.contains((4, 12)));
+        assert!(!diamond.contains((-1, 6)));
+        assert_eq!(diamond.row(7), Some((-1, 17)));
+        assert_eq!(diamond.row(10), Some((2, 14)));
+        assert_eq!(diamond.row(16), Some((8, 8)));
+        assert_eq!(diamond.row(17), None);
+    }
+
+    #[test]
+    fn test_boundary_intersections() {
+        // a point sitting just outside the corner of a diamond
+        let diamond = Diamond::new((0, 0), 2);
+        let candidates = boundary_intersections(&[diamond], 1);
+        assert!(candidates.contains(&(3, 0)));
+        assert!(candidates.contains(&(0, -3)));
+        assert!(candidates
+            .iter()
+            .all(|point| manhattan(diamond.center, *point) == 3));
+    }
+}
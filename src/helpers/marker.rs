@@ -0,0 +1,129 @@
+// Markers in a stream: the places where the last few bytes are all different,
+// like day 6's start-of-packet and start-of-message markers.
+//
+// The window slides over the stream one byte at a time while keeping a count of
+// every byte value in it, and of how many values are in it more than once. A
+// byte entering or leaving the window only changes those counts by one, so the
+// whole stream takes O(n) however wide the window is.
+use std::io::{self, BufReader, Bytes, Read};
+
+/// The markers in a stream, found as the stream is read. Each one is the
+/// position (the number of bytes read so far) at which the last `width` bytes
+/// were all different.
+pub struct Markers<R> {
+    bytes: Bytes<BufReader<R>>,
+    width: usize,
+    counts: [usize; 256],
+    repeated: usize,
+    // The bytes in the window, in a ring starting at the oldest
+    window: Vec<u8>,
+    read: usize,
+}
+
+impl<R: Read> Markers<R> {
+    pub fn new(reader: R, width: usize) -> Self {
+        assert!(width > 0, "Markers need at least one byte");
+        Self {
+            bytes: BufReader::new(reader).bytes(),
+            width,
+            counts: [0; 256],
+            repeated: 0,
+            window: Vec::with_capacity(width),
+            read: 0,
+        }
+    }
+}
+
+impl<R: Read> Iterator for Markers<R> {
+    type Item = io::Result<usize>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for byte in self.bytes.by_ref() {
+            let byte = match byte {
+                Ok(byte) => byte,
+                Err(error) => return Some(Err(error)),
+            };
+            if self.window.len() == self.width {
+                let oldest = std::mem::replace(&mut self.window[self.read % self.width], byte);
+                self.counts[oldest as usize] -= 1;
+                if self.counts[oldest as usize] == 1 {
+                    self.repeated -= 1;
+                }
+            } else {
+                self.window.push(byte);
+            }
+            self.counts[byte as usize] += 1;
+            if self.counts[byte as usize] == 2 {
+                self.repeated += 1;
+            }
+            self.read += 1;
+            if self.window.len() == self.width && self.repeated == 0 {
+                return Some(Ok(self.read));
+            }
+        }
+        None
+    }
+}
+
+/// Every marker in `reader`, see `Markers`.
+pub fn markers(reader: impl Read, width: usize) -> io::Result<Vec<usize>> {
+    Markers::new(reader, width).collect()
+}
+
+/// The first marker in `reader`, without reading any further than that.
+pub fn first(reader: impl Read, width: usize) -> io::Result<Option<usize>> {
+    Markers::new(reader, width).next().transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_examples() {
+        for (signal, packet, message) in [
+            ("mjqjpqmgbljsphdztnvjfqwrcgsmlb", 7, 19),
+            ("bvwbjplbgvbhsrlpgdmjqwftvncz", 5, 23),
+            ("nppdvjthqldpwncqszvftbrmjlhg", 6, 23),
+            ("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg", 10, 29),
+            ("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw", 11, 26),
+        ] {
+            assert_eq!(markers(signal.as_bytes(), 4).unwrap()[0], packet);
+            assert_eq!(markers(signal.as_bytes(), 14).unwrap()[0], message);
+        }
+    }
+
+    #[test]
+    fn test_all_markers() {
+        assert_eq!(markers("abcabc".as_bytes(), 3).unwrap(), vec![3, 4, 5, 6]);
+        assert_eq!(markers("aabba".as_bytes(), 2).unwrap(), vec![3, 5]);
+        assert_eq!(markers("aaaa".as_bytes(), 2).unwrap(), vec![]);
+        assert_eq!(markers("ab".as_bytes(), 3).unwrap(), vec![]);
+        // The window carries on across reads
+        let stream = "mjqjp"
+            .as_bytes()
+            .chain("qmgbljsphdztnvjfqwrcgsmlb".as_bytes());
+        assert_eq!(
+            markers(stream, 14).unwrap(),
+            vec![19, 25, 26, 27, 28, 29, 30]
+        );
+    }
+
+    // Reading from this always fails
+    struct Broken;
+
+    impl Read for Broken {
+        fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+            Err(io::Error::other("broken stream"))
+        }
+    }
+
+    #[test]
+    fn test_first() {
+        assert_eq!(first("aabcd".as_bytes(), 4).unwrap(), Some(5));
+        assert_eq!(first("aaaa".as_bytes(), 2).unwrap(), None);
+        // Nothing after the first marker is read
+        assert_eq!(first("abcd".as_bytes().chain(Broken), 4).unwrap(), Some(4));
+        assert!(markers("abcd".as_bytes().chain(Broken), 4).is_err());
+    }
+}
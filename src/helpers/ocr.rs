@@ -0,0 +1,184 @@
+// Reads the block letters some puzzles draw instead of printing an answer.
+//
+// AoC uses two fonts: a small one, 6 pixels high and mostly 4 wide, and a
+// large one, 10 pixels high and 6 wide. Letters are split on blank columns and
+// trimmed before they're looked up, so the spacing between them doesn't matter.
+// Only the letters that have actually shown up in puzzles are known.
+
+const SMALL: [(char, &str); 18] = [
+    ('A', ".##.\n#..#\n#..#\n####\n#..#\n#..#"),
+    ('B', "###.\n#..#\n###.\n#..#\n#..#\n###."),
+    ('C', ".##.\n#..#\n#...\n#...\n#..#\n.##."),
+    ('E', "####\n#...\n###.\n#...\n#...\n####"),
+    ('F', "####\n#...\n###.\n#...\n#...\n#..."),
+    ('G', ".##.\n#..#\n#...\n#.##\n#..#\n.###"),
+    ('H', "#..#\n#..#\n####\n#..#\n#..#\n#..#"),
+    ('I', "###\n.#.\n.#.\n.#.\n.#.\n###"),
+    ('J', "..##\n...#\n...#\n...#\n#..#\n.##."),
+    ('K', "#..#\n#.#.\n##..\n#.#.\n#.#.\n#..#"),
+    ('L', "#...\n#...\n#...\n#...\n#...\n####"),
+    ('O', ".##.\n#..#\n#..#\n#..#\n#..#\n.##."),
+    ('P', "###.\n#..#\n#..#\n###.\n#...\n#..."),
+    ('R', "###.\n#..#\n#..#\n###.\n#.#.\n#..#"),
+    ('S', ".###\n#...\n#...\n.##.\n...#\n###."),
+    ('U', "#..#\n#..#\n#..#\n#..#\n#..#\n.##."),
+    ('Y', "#...#\n#...#\n.#.#.\n..#..\n..#..\n..#.."),
+    ('Z', "####\n...#\n..#.\n.#..\n#...\n####"),
+];
+
+const LARGE: [(char, &str); 15] = [
+    (
+        'A',
+        "..##..\n.#..#.\n#....#\n#....#\n#....#\n######\n#....#\n#....#\n#....#\n#....#",
+    ),
+    (
+        'B',
+        "#####.\n#....#\n#....#\n#....#\n#####.\n#....#\n#....#\n#....#\n#....#\n#####.",
+    ),
+    (
+        'C',
+        ".####.\n#....#\n#.....\n#.....\n#.....\n#.....\n#.....\n#.....\n#....#\n.####.",
+    ),
+    (
+        'E',
+        "######\n#.....\n#.....\n#.....\n#####.\n#.....\n#.....\n#.....\n#.....\n######",
+    ),
+    (
+        'F',
+        "######\n#.....\n#.....\n#.....\n#####.\n#.....\n#.....\n#.....\n#.....\n#.....",
+    ),
+    (
+        'G',
+        ".####.\n#....#\n#.....\n#.....\n#.....\n#..###\n#....#\n#....#\n#...##\n.###.#",
+    ),
+    (
+        'H',
+        "#....#\n#....#\n#....#\n#....#\n######\n#....#\n#....#\n#....#\n#....#\n#....#",
+    ),
+    (
+        'J',
+        "...###\n....#.\n....#.\n....#.\n....#.\n....#.\n....#.\n#...#.\n#...#.\n.###..",
+    ),
+    (
+        'K',
+        "#....#\n#...#.\n#..#..\n#.#...\n##....\n##....\n#.#...\n#..#..\n#...#.\n#....#",
+    ),
+    (
+        'L',
+        "#.....\n#.....\n#.....\n#.....\n#.....\n#.....\n#.....\n#.....\n#.....\n######",
+    ),
+    (
+        'N',
+        "#....#\n##...#\n##...#\n#.#..#\n#.#..#\n#..#.#\n#..#.#\n#...##\n#...##\n#....#",
+    ),
+    (
+        'P',
+        "#####.\n#....#\n#....#\n#....#\n#####.\n#.....\n#.....\n#.....\n#.....\n#.....",
+    ),
+    (
+        'R',
+        "#####.\n#....#\n#....#\n#....#\n#####.\n#..#..\n#...#.\n#...#.\n#....#\n#....#",
+    ),
+    (
+        'X',
+        "#....#\n#....#\n.#..#.\n.#..#.\n..##..\n..##..\n.#..#.\n.#..#.\n#....#\n#....#",
+    ),
+    (
+        'Z',
+        "######\n.....#\n.....#\n....#.\n...#..\n..#...\n.#....\n#.....\n#.....\n######",
+    ),
+];
+
+/// Turns an image of `#` (lit) and `.` or ` ` (dark) pixels into the letters
+/// it shows. The font is picked from the height of the image.
+pub fn recognize(image: &str) -> Result<String, String> {
+    let rows: Vec<Vec<bool>> = image
+        .lines()
+        .map(|line| line.chars().map(|c| c == '#').collect())
+        .collect();
+    let alphabet: &[(char, &str)] = match rows.len() {
+        6 => &SMALL,
+        10 => &LARGE,
+        height => return Err(format!("No font is {} pixels high", height)),
+    };
+    let width = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+    let lit = |x: usize| rows.iter().any(|row| row.get(x) == Some(&true));
+
+    let mut letters = String::new();
+    let mut x = 0;
+    while x < width {
+        if !lit(x) {
+            x += 1;
+            continue;
+        }
+        let start = x;
+        while x < width && lit(x) {
+            x += 1;
+        }
+        let glyph: Vec<String> = rows
+            .iter()
+            .map(|row| {
+                (start..x)
+                    .map(|i| if row.get(i) == Some(&true) { '#' } else { '.' })
+                    .collect()
+            })
+            .collect();
+        let glyph = glyph.join("\n");
+        let letter = alphabet
+            .iter()
+            .find(|(_, pattern)| *pattern == glyph)
+            .map(|(letter, _)| *letter)
+            .ok_or_else(|| format!("Unknown letter at column {}:\n{}", start + 1, glyph))?;
+        letters.push(letter);
+    }
+    Ok(letters)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recognize_small() {
+        let image = [
+            "###..#....####.####.#..#.#..#.###..#..#.",
+            "#..#.#....#.......#.#..#.#..#.#..#.#..#.",
+            "#..#.#....###....#..####.#..#.#..#.####.",
+            "###..#....#.....#...#..#.#..#.###..#..#.",
+            "#....#....#....#....#..#.#..#.#.#..#..#.",
+            "#....####.####.####.#..#..##..#..#.#..#.",
+        ]
+        .join("\n");
+        assert_eq!(recognize(&image), Ok("PLEZHURH".to_string()));
+    }
+
+    #[test]
+    fn test_recognize_large() {
+        let image = [
+            "#....#..######...####",
+            "#....#.......#..#....",
+            ".#..#........#..#....",
+            ".#..#.......#...#....",
+            "..##.......#....#....",
+            "..##......#.....#....",
+            ".#..#....#......#....",
+            ".#..#...#.......#....",
+            "#....#..#.......#....",
+            "#....#..######...####",
+        ]
+        .join("\n");
+        assert!(recognize(&image).is_err());
+        let image = image
+            .lines()
+            .map(|line| &line[..14])
+            .collect::<Vec<_>>()
+            .join("\n");
+        assert_eq!(recognize(&image), Ok("XZ".to_string()));
+    }
+
+    #[test]
+    fn test_recognize_unknown() {
+        assert!(recognize("#\n#").is_err());
+        assert!(recognize("#.#\n###\n#.#\n#.#\n#.#\n#.#").is_err());
+    }
+}
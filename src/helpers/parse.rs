@@ -0,0 +1,192 @@
+// Small parsers for the shapes AoC inputs keep coming in.
+//
+// Errors carry the 1-based line and column (relative to the string that was
+// passed in) so a bad puzzle input points straight at the offending character.
+use std::fmt::Display;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl ParseError {
+    pub fn new(line: usize, column: usize, message: impl Into<String>) -> Self {
+        Self {
+            line,
+            column,
+            message: message.into(),
+        }
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Every integer in `input`, in order. A `-` directly in front of a number
+/// makes it negative, unless it follows a letter or digit (so the ranges in
+/// `2-4,6-8` are still read as positive numbers).
+pub fn integers(input: &str) -> Result<Vec<i64>, ParseError> {
+    let mut numbers = Vec::new();
+    for (line_index, line) in input.lines().enumerate() {
+        let chars: Vec<char> = line.chars().collect();
+        let mut i = 0;
+        while i < chars.len() {
+            let negative = chars[i] == '-'
+                && chars.get(i + 1).is_some_and(|c| c.is_ascii_digit())
+                && (i == 0 || !chars[i - 1].is_ascii_alphanumeric());
+            if !negative && !chars[i].is_ascii_digit() {
+                i += 1;
+                continue;
+            }
+            let start = i;
+            i += 1;
+            while i < chars.len() && chars[i].is_ascii_digit() {
+                i += 1;
+            }
+            let text: String = chars[start..i].iter().collect();
+            let number = text.parse::<i64>().map_err(|e| {
+                ParseError::new(line_index + 1, start + 1, format!("{}: {}", text, e))
+            })?;
+            numbers.push(number);
+        }
+    }
+    Ok(numbers)
+}
+
+/// Splits `input` into the chunks separated by blank lines.
+pub fn blocks(input: &str) -> Vec<&str> {
+    let mut blocks = Vec::new();
+    let mut start: Option<usize> = None;
+    let mut end = 0;
+    let mut offset = 0;
+    for line in input.split_inclusive('\n') {
+        if line.trim().is_empty() {
+            if let Some(s) = start.take() {
+                blocks.push(&input[s..end]);
+            }
+        } else {
+            start.get_or_insert(offset);
+            end = offset + line.trim_end_matches(['\r', '\n']).len();
+        }
+        offset += line.len();
+    }
+    if let Some(s) = start {
+        blocks.push(&input[s..end]);
+    }
+    blocks
+}
+
+/// Reads a fixed-width layout column by column, like the crate stacks on day
+/// 5 where a label sits every `stride` characters starting at `offset`.
+/// `cell` turns each character into a value, `None` for an empty cell. Each
+/// column is returned top to bottom; short lines just have empty cells.
+pub fn columns<T, F>(
+    input: &str,
+    offset: usize,
+    stride: usize,
+    mut cell: F,
+) -> Result<Vec<Vec<T>>, ParseError>
+where
+    F: FnMut(char) -> Result<Option<T>, String>,
+{
+    let mut columns: Vec<Vec<T>> = Vec::new();
+    for (line_index, line) in input.lines().enumerate() {
+        for (index, c) in line.chars().skip(offset).step_by(stride).enumerate() {
+            if columns.len() <= index {
+                columns.resize_with(index + 1, Vec::new);
+            }
+            let column = offset + index * stride;
+            match cell(c) {
+                Ok(Some(value)) => columns[index].push(value),
+                Ok(None) => {}
+                Err(message) => return Err(ParseError::new(line_index + 1, column + 1, message)),
+            }
+        }
+    }
+    Ok(columns)
+}
+
+/// Reads `key: value` lines, trimming whitespace around both halves. The
+/// key is everything up to the first colon.
+pub fn records(input: &str) -> Result<Vec<(&str, &str)>, ParseError> {
+    input
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(line_index, line)| match line.split_once(':') {
+            Some((key, value)) => Ok((key.trim(), value.trim())),
+            None => Err(ParseError::new(
+                line_index + 1,
+                line.len() + 1,
+                format!("expected `key: value`, found {:?}", line),
+            )),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_integers() {
+        assert_eq!(
+            integers("Sensor at x=2, y=-18: closest beacon is at x=-2, y=15").unwrap(),
+            vec![2, -18, -2, 15]
+        );
+        assert_eq!(integers("2-4,6-8\n").unwrap(), vec![2, 4, 6, 8]);
+        assert_eq!(integers("move 1 from 2 to 1").unwrap(), vec![1, 2, 1]);
+        let error = integers("ok 1\nx=99999999999999999999").unwrap_err();
+        assert_eq!((error.line, error.column), (2, 3));
+    }
+
+    #[test]
+    fn test_blocks() {
+        let input = "1000\n2000\n\n4000\r\n\r\n\n5000\n6000\n";
+        assert_eq!(blocks(input), vec!["1000\n2000", "4000", "5000\n6000"]);
+        assert!(blocks("\n\n").is_empty());
+    }
+
+    #[test]
+    fn test_columns() {
+        let input = "    [D]    \n[N] [C]    \n[Z] [M] [P]";
+        let parsed = columns(input, 1, 4, |c| match c {
+            ' ' => Ok(None),
+            c if c.is_ascii_uppercase() => Ok(Some(c)),
+            c => Err(format!("invalid crate {:?}", c)),
+        })
+        .unwrap();
+        assert_eq!(parsed, vec![vec!['N', 'Z'], vec!['D', 'C', 'M'], vec!['P']]);
+
+        let error = columns("[A] [b]", 1, 4, |c| match c {
+            c if c.is_ascii_uppercase() => Ok(Some(c)),
+            c => Err(format!("invalid crate {:?}", c)),
+        })
+        .unwrap_err();
+        assert_eq!((error.line, error.column), (1, 6));
+    }
+
+    #[test]
+    fn test_records() {
+        let input = "Monkey 0:\n  Starting items: 79, 98\n  Test: divisible by 23";
+        assert_eq!(
+            records(input).unwrap(),
+            vec![
+                ("Monkey 0", ""),
+                ("Starting items", "79, 98"),
+                ("Test", "divisible by 23")
+            ]
+        );
+        assert_eq!(
+            records("a: 1\nb").unwrap_err().to_string().get(..4),
+            Some("2:2:")
+        );
+    }
+}
@@ -0,0 +1,268 @@
+// Particles dropping one at a time from a source into a cave of solid rock,
+// like the sand in day 14.
+//
+// A particle tries each fall direction in order and comes to rest once none of
+// them are free. Every particle starts where the previous one was just before
+// it settled: the part of the path above that is the same for everyone, so the
+// engine keeps the path as a stack and never walks it again.
+//
+// The cave is a dense grid covering everything a particle can reach. Without a
+// floor that's the rocks' bounding box, and leaving it means falling forever.
+// With a floor the grid is widened to how far particles can spread before
+// they hit it.
+
+/// What's in a cell of the cave.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cell {
+    Empty,
+    Rock,
+    Particle,
+}
+
+/// What happened to a dropped particle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Settled((i64, i64)),
+    /// Left the grid and will keep falling forever.
+    Escaped,
+    /// The source itself is filled, nothing can be dropped any more.
+    Blocked,
+}
+
+#[derive(Debug, Clone)]
+struct Grid {
+    x_min: i64,
+    y_min: i64,
+    width: usize,
+    height: usize,
+    cells: Vec<Cell>,
+}
+
+impl Grid {
+    fn index(&self, (x, y): (i64, i64)) -> Option<usize> {
+        let (column, row) = (x - self.x_min, y - self.y_min);
+        if column < 0 || row < 0 || column as usize >= self.width || row as usize >= self.height {
+            return None;
+        }
+        Some(row as usize * self.width + column as usize)
+    }
+
+    fn get(&self, position: (i64, i64)) -> Option<Cell> {
+        self.index(position).map(|i| self.cells[i])
+    }
+
+    fn set(&mut self, position: (i64, i64), cell: Cell) {
+        if let Some(i) = self.index(position) {
+            self.cells[i] = cell;
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Engine {
+    rocks: Vec<(i64, i64)>,
+    source: (i64, i64),
+    directions: Vec<(i64, i64)>,
+    floor: Option<i64>,
+    grid: Grid,
+    // Where the current particle has been on its way down, source first
+    path: Vec<(i64, i64)>,
+    settled: usize,
+}
+
+impl Engine {
+    /// A cave without a floor where particles fall straight down, then down
+    /// and to the left, then down and to the right. `y` grows downwards.
+    pub fn new(rocks: impl IntoIterator<Item = (i64, i64)>, source: (i64, i64)) -> Self {
+        let rocks: Vec<(i64, i64)> = rocks.into_iter().collect();
+        let mut engine = Self {
+            grid: Grid {
+                x_min: 0,
+                y_min: 0,
+                width: 0,
+                height: 0,
+                cells: vec![],
+            },
+            rocks,
+            source,
+            directions: vec![(0, 1), (-1, 1), (1, 1)],
+            floor: None,
+            path: vec![],
+            settled: 0,
+        };
+        engine.reset();
+        engine
+    }
+
+    /// The moves a particle tries, in order. All of them have to go down.
+    pub fn with_directions(mut self, directions: &[(i64, i64)]) -> Self {
+        assert!(
+            directions.iter().all(|(_, dy)| *dy > 0),
+            "particles can only fall down"
+        );
+        self.directions = directions.to_vec();
+        self.reset();
+        self
+    }
+
+    /// An endless row of rock at height `y`.
+    pub fn with_floor(mut self, y: i64) -> Self {
+        self.floor = Some(y);
+        self.reset();
+        self
+    }
+
+    // Empties the cave and sizes the grid for the current configuration
+    fn reset(&mut self) {
+        let xs = self.rocks.iter().map(|(x, _)| *x).chain([self.source.0]);
+        let (mut x_min, mut x_max) = (xs.clone().min().unwrap(), xs.max().unwrap());
+        let y_min = self.source.1;
+        let mut y_max = self.rocks.iter().map(|(_, y)| *y).max().unwrap_or(y_min);
+        if let Some(floor) = self.floor {
+            let reach = self
+                .directions
+                .iter()
+                .map(|(dx, _)| dx.abs())
+                .max()
+                .unwrap_or(0);
+            let spread = reach * (floor - y_min);
+            x_min = x_min.min(self.source.0 - spread);
+            x_max = x_max.max(self.source.0 + spread);
+            y_max = floor;
+        }
+        let width = (x_max - x_min + 1) as usize;
+        let height = (y_max - y_min + 1) as usize;
+        self.grid = Grid {
+            x_min,
+            y_min,
+            width,
+            height,
+            cells: vec![Cell::Empty; width * height],
+        };
+        for &rock in &self.rocks {
+            self.grid.set(rock, Cell::Rock);
+        }
+        if let Some(floor) = self.floor {
+            for x in x_min..=x_max {
+                self.grid.set((x, floor), Cell::Rock);
+            }
+        }
+        self.path.clear();
+        self.settled = 0;
+    }
+
+    pub fn get(&self, position: (i64, i64)) -> Cell {
+        self.grid.get(position).unwrap_or(Cell::Empty)
+    }
+
+    /// How many particles have come to rest so far.
+    pub fn settled(&self) -> usize {
+        self.settled
+    }
+
+    /// Drops a single particle.
+    pub fn drop_one(&mut self) -> Outcome {
+        if self.path.is_empty() {
+            if self.get(self.source) != Cell::Empty {
+                return Outcome::Blocked;
+            }
+            self.path.push(self.source);
+        }
+        loop {
+            let (x, y) = *self.path.last().unwrap();
+            let mut next = None;
+            for (dx, dy) in &self.directions {
+                let position = (x + dx, y + dy);
+                match self.grid.get(position) {
+                    None => return Outcome::Escaped,
+                    Some(Cell::Empty) => {
+                        next = Some(position);
+                        break;
+                    }
+                    Some(_) => {}
+                }
+            }
+            match next {
+                Some(position) => self.path.push(position),
+                None => {
+                    self.path.pop();
+                    self.grid.set((x, y), Cell::Particle);
+                    self.settled += 1;
+                    return Outcome::Settled((x, y));
+                }
+            }
+        }
+    }
+
+    /// Drops particles until `stop` returns true for one of them, or the
+    /// source is blocked. Returns how many particles settled in total.
+    /// Once one particle escapes they all do, so stop on that at the latest
+    /// if there's no floor.
+    pub fn run<F>(&mut self, mut stop: F) -> usize
+    where
+        F: FnMut(&Self, Outcome) -> bool,
+    {
+        loop {
+            let outcome = self.drop_one();
+            if outcome == Outcome::Blocked || stop(self, outcome) {
+                return self.settled;
+            }
+        }
+    }
+
+    /// The cave as text: `#` for rock, `o` for particles at rest, `~` for the
+    /// path the next particle starts from, and `+` for the source.
+    pub fn frame(&self) -> String {
+        let mut rows = vec![];
+        for row in 0..self.grid.height {
+            let y = self.grid.y_min + row as i64;
+            let row: String = (0..self.grid.width)
+                .map(|column| {
+                    let position = (self.grid.x_min + column as i64, y);
+                    match self.get(position) {
+                        Cell::Rock => '#',
+                        Cell::Particle => 'o',
+                        Cell::Empty if position == self.source => '+',
+                        Cell::Empty if self.path.contains(&position) => '~',
+                        Cell::Empty => '.',
+                    }
+                })
+                .collect();
+            rows.push(row);
+        }
+        rows.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A cup with room for two particles, and one more on top
+    fn cup() -> Engine {
+        let rocks = [(0, 1), (0, 2), (1, 2), (2, 2), (3, 2), (3, 1)];
+        Engine::new(rocks, (1, 0))
+    }
+
+    #[test]
+    fn test_overflow() {
+        let mut engine = cup();
+        assert_eq!(engine.drop_one(), Outcome::Settled((1, 1)));
+        assert_eq!(engine.drop_one(), Outcome::Settled((2, 1)));
+        assert_eq!(engine.drop_one(), Outcome::Settled((1, 0)));
+        assert_eq!(engine.drop_one(), Outcome::Blocked);
+        assert_eq!(engine.frame(), ".o..\n#oo#\n####");
+        // the second particle rolls off the edge of a shelf
+        let mut engine = Engine::new([(0, 2), (1, 2), (2, 2)], (1, 0));
+        assert_eq!(engine.run(|_, outcome| outcome == Outcome::Escaped), 1);
+        let mut engine = cup().with_directions(&[(0, 1), (-2, 1)]);
+        assert_eq!(engine.drop_one(), Outcome::Escaped);
+    }
+
+    #[test]
+    fn test_floor() {
+        let mut engine = Engine::new([], (0, 0)).with_floor(2);
+        assert_eq!(engine.run(|_, _| false), 4);
+        assert_eq!(engine.frame(), "..o..\n.ooo.\n#####");
+    }
+}
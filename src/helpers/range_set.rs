@@ -0,0 +1,183 @@
+// A set of integers stored as sorted, disjoint closed intervals.
+//
+// Sweep-line puzzles (day 15's sensor coverage, day 4's section assignments)
+// tend to produce lots of overlapping ranges along a single axis. Keeping them
+// merged means questions like "how many cells are covered?" or "where's the
+// gap?" only cost one pass over the intervals instead of one per cell.
+use std::cmp::{max, min};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RangeSet {
+    // Sorted by start, never overlapping or touching
+    ranges: Vec<(i64, i64)>,
+}
+
+impl RangeSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The merged intervals, in ascending order.
+    pub fn ranges(&self) -> &[(i64, i64)] {
+        &self.ranges
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    /// Total number of integers covered.
+    pub fn len(&self) -> u64 {
+        self.ranges
+            .iter()
+            .map(|(start, end)| (end - start + 1) as u64)
+            .sum()
+    }
+
+    pub fn contains(&self, value: i64) -> bool {
+        // index of the first range starting after `value`
+        let index = self.ranges.partition_point(|(start, _)| *start <= value);
+        index > 0 && self.ranges[index - 1].1 >= value
+    }
+
+    /// Adds the closed interval `start..=end`, merging it with any ranges it
+    /// overlaps or touches. Empty intervals (`start > end`) are ignored.
+    pub fn insert(&mut self, start: i64, end: i64) {
+        if start > end {
+            return;
+        }
+        // ranges entirely before the new one, ignoring touching neighbours
+        let first = self.ranges.partition_point(|(_, e)| *e < start - 1);
+        // ranges that start at or before the end of the new one (plus touching)
+        let last = self.ranges.partition_point(|(s, _)| *s <= end + 1);
+        let mut merged = (start, end);
+        if first < last {
+            merged.0 = min(merged.0, self.ranges[first].0);
+            merged.1 = max(merged.1, self.ranges[last - 1].1);
+        }
+        self.ranges.splice(first..last, [merged]);
+    }
+
+    /// Removes a single value, splitting the range that contains it.
+    pub fn remove(&mut self, value: i64) {
+        let index = self.ranges.partition_point(|(start, _)| *start <= value);
+        if index == 0 || self.ranges[index - 1].1 < value {
+            return;
+        }
+        let (start, end) = self.ranges[index - 1];
+        let pieces = [(start, value - 1), (value + 1, end)];
+        self.ranges.splice(
+            index - 1..index,
+            pieces.into_iter().filter(|(start, end)| start <= end),
+        );
+    }
+
+    /// Values in `start..=end` that are not covered, as merged intervals.
+    pub fn gaps(&self, start: i64, end: i64) -> RangeSet {
+        let mut gaps = RangeSet::new();
+        let mut cursor = start;
+        for &(range_start, range_end) in &self.ranges {
+            if range_end < cursor {
+                continue;
+            }
+            if range_start > end {
+                break;
+            }
+            if range_start > cursor {
+                gaps.ranges.push((cursor, range_start - 1));
+            }
+            cursor = range_end + 1;
+            if cursor > end {
+                return gaps;
+            }
+        }
+        if cursor <= end {
+            gaps.ranges.push((cursor, end));
+        }
+        gaps
+    }
+
+    /// Values covered by both sets.
+    pub fn intersection(&self, other: &RangeSet) -> RangeSet {
+        let mut result = RangeSet::new();
+        let (mut i, mut j) = (0, 0);
+        while i < self.ranges.len() && j < other.ranges.len() {
+            let (a_start, a_end) = self.ranges[i];
+            let (b_start, b_end) = other.ranges[j];
+            let start = max(a_start, b_start);
+            let end = min(a_end, b_end);
+            if start <= end {
+                result.ranges.push((start, end));
+            }
+            // whichever range ends first can't overlap anything else
+            if a_end < b_end {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+        result
+    }
+}
+
+impl FromIterator<(i64, i64)> for RangeSet {
+    // Sorting up front and merging in one pass is O(n log n), which beats
+    // calling `insert` for every interval
+    fn from_iter<I: IntoIterator<Item = (i64, i64)>>(iter: I) -> Self {
+        let mut sorted: Vec<(i64, i64)> = iter
+            .into_iter()
+            .filter(|(start, end)| start <= end)
+            .collect();
+        sorted.sort_unstable();
+        let mut ranges: Vec<(i64, i64)> = Vec::with_capacity(sorted.len());
+        for (start, end) in sorted {
+            match ranges.last_mut() {
+                Some(last) if start <= last.1 + 1 => last.1 = max(last.1, end),
+                _ => ranges.push((start, end)),
+            }
+        }
+        RangeSet { ranges }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge() {
+        let set: RangeSet = vec![(12, 12), (2, 14), (2, 2), (-2, 2), (16, 24), (14, 18)]
+            .into_iter()
+            .collect();
+        assert_eq!(set.ranges(), &[(-2, 24)]);
+        assert_eq!(set.len(), 27);
+
+        let mut inserted = RangeSet::new();
+        for (start, end) in [(5, 6), (1, 2), (10, 12), (3, 3), (8, 9)] {
+            inserted.insert(start, end);
+        }
+        assert_eq!(inserted.ranges(), &[(1, 3), (5, 6), (8, 12)]);
+        assert!(inserted.contains(8));
+        assert!(!inserted.contains(4));
+        assert!(!inserted.contains(0));
+    }
+
+    #[test]
+    fn test_remove_and_gaps() {
+        let mut set: RangeSet = vec![(0, 10), (14, 20)].into_iter().collect();
+        set.remove(0);
+        set.remove(5);
+        set.remove(12);
+        assert_eq!(set.ranges(), &[(1, 4), (6, 10), (14, 20)]);
+        assert_eq!(set.gaps(0, 20).ranges(), &[(0, 0), (5, 5), (11, 13)]);
+        assert_eq!(set.gaps(2, 3).ranges(), &[]);
+        assert_eq!(set.gaps(18, 25).ranges(), &[(21, 25)]);
+    }
+
+    #[test]
+    fn test_intersection() {
+        let a: RangeSet = vec![(0, 5), (10, 15)].into_iter().collect();
+        let b: RangeSet = vec![(3, 12), (15, 30)].into_iter().collect();
+        assert_eq!(a.intersection(&b).ranges(), &[(3, 5), (10, 12), (15, 15)]);
+    }
+}
@@ -0,0 +1,114 @@
+// Breadth-first search over a "position × time" state space.
+//
+// Some puzzles (day 24's blizzard basin, for example) have obstacles that move
+// on a fixed schedule, so whether a position is open depends on the minute we
+// arrive there. Because the schedule repeats every `period` minutes, two visits
+// to the same position at the same `time % period` are equivalent, which keeps
+// the state space finite without ever building it up front: neighbours are
+// generated lazily and checked against a time-indexed occupancy function.
+use std::collections::HashSet;
+use std::hash::Hash;
+use std::iter::once;
+
+pub struct TimedSearch<N, O> {
+    period: usize,
+    neighbours: N,
+    open: O,
+}
+
+impl<N, O> TimedSearch<N, O> {
+    /// `neighbours` returns the positions reachable from a position in one
+    /// step (waiting in place is always considered, so it shouldn't be
+    /// included). `open` tells whether a position can be occupied at a given
+    /// time.
+    pub fn new(period: usize, neighbours: N, open: O) -> Self {
+        assert!(period > 0, "period must be at least 1");
+        Self {
+            period,
+            neighbours,
+            open,
+        }
+    }
+
+    /// Returns the earliest time we can stand on `goal` when leaving `start`
+    /// at `time`, or `None` if it can never be reached. Instead of queueing
+    /// states it moves the set of every position we could be in forward a
+    /// minute at a time. When lots of routes lead to the same places, like in
+    /// an open valley, that set stays much smaller than a queue would.
+    pub fn frontier<P, I>(&self, start: P, goal: P, time: usize) -> Option<usize>
+    where
+        P: Copy + Eq + Hash,
+        N: Fn(P) -> I,
+        I: IntoIterator<Item = P>,
+        O: Fn(P, usize) -> bool,
+    {
+        let mut frontier = HashSet::from([start]);
+        let mut seen = HashSet::from([(start, time % self.period)]);
+        let mut time = time;
+        while !frontier.contains(&goal) {
+            if frontier.is_empty() {
+                return None;
+            }
+            time += 1;
+            frontier = frontier
+                .iter()
+                .flat_map(|&position| once(position).chain((self.neighbours)(position)))
+                .filter(|&next| (self.open)(next, time) && seen.insert((next, time % self.period)))
+                .collect();
+        }
+        Some(time)
+    }
+
+    /// Visits each of `waypoints` in order, starting from the first one at
+    /// `time`. Each leg starts the minute the previous one arrived, so
+    /// `[start, goal, start, goal]` answers a there-and-back-again trip in a
+    /// single query. Returns the arrival time of every leg, each found with
+    /// `frontier`.
+    pub fn journey<P, I>(&self, waypoints: &[P], time: usize) -> Option<Vec<usize>>
+    where
+        P: Copy + Eq + Hash,
+        N: Fn(P) -> I,
+        I: IntoIterator<Item = P>,
+        O: Fn(P, usize) -> bool,
+    {
+        let mut arrivals = Vec::with_capacity(waypoints.len().saturating_sub(1));
+        let mut time = time;
+        for leg in waypoints.windows(2) {
+            time = self.frontier(leg[0], leg[1], time)?;
+            arrivals.push(time);
+        }
+        Some(arrivals)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A corridor 0..=4 with a gate at position 2 that is only open on even minutes.
+    fn corridor() -> TimedSearch<impl Fn(i32) -> Vec<i32>, impl Fn(i32, usize) -> bool> {
+        TimedSearch::new(
+            2,
+            |p: i32| vec![p - 1, p + 1],
+            |p: i32, t: usize| (0..=4).contains(&p) && (p != 2 || t.is_multiple_of(2)),
+        )
+    }
+
+    #[test]
+    fn test_frontier_waits_for_gate() {
+        let search = corridor();
+        // leaving at t=0 we reach the gate at t=2 while it is open
+        assert_eq!(search.frontier(0, 4, 0), Some(4));
+        // leaving at t=1 we reach 1 at t=2 and must wait for the gate at t=4
+        assert_eq!(search.frontier(0, 4, 1), Some(6));
+        assert_eq!(search.frontier(0, 4, 2), Some(6));
+        assert_eq!(search.frontier(0, 5, 0), None);
+    }
+
+    #[test]
+    fn test_journey() {
+        let search = corridor();
+        assert_eq!(search.journey(&[0, 4, 0], 0), Some(vec![4, 8]));
+        assert_eq!(search.journey(&[0], 0), Some(vec![]));
+    }
+}
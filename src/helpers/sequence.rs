@@ -0,0 +1,174 @@
+// A list that's cheap to reorder, for puzzles that keep moving elements
+// around while asking where they ended up (like day 20's mixing).
+//
+// Elements are split into blocks of about √n. Finding an element's position,
+// removing it, or inserting it somewhere only has to look at the block sizes
+// and a single block, so each of those takes O(√n) instead of O(n). Every
+// element has an id (its index in the original order) which it keeps wherever
+// it goes. When a block has grown to twice its intended size the blocks are
+// rebuilt, which costs O(n) but doesn't happen more often than every √n
+// insertions.
+
+#[derive(Debug, Clone)]
+pub struct Sequence<T> {
+    values: Vec<T>,
+    // The ids of the elements in each block, in order
+    blocks: Vec<Vec<usize>>,
+    // Which block each id is in
+    block_of: Vec<usize>,
+    block_size: usize,
+}
+
+impl<T> FromIterator<T> for Sequence<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let values: Vec<T> = iter.into_iter().collect();
+        let mut sequence = Self {
+            block_size: (values.len() as f64).sqrt().ceil().max(1.0) as usize,
+            block_of: vec![0; values.len()],
+            blocks: vec![],
+            values,
+        };
+        sequence.rebuild((0..sequence.values.len()).collect());
+        sequence
+    }
+}
+
+impl<T> Sequence<T> {
+    fn rebuild(&mut self, order: Vec<usize>) {
+        self.blocks = order
+            .chunks(self.block_size)
+            .map(|chunk| chunk.to_vec())
+            .collect();
+        for (b, block) in self.blocks.iter().enumerate() {
+            for &id in block {
+                self.block_of[id] = b;
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// The value of the element with id `id`.
+    pub fn value(&self, id: usize) -> &T {
+        &self.values[id]
+    }
+
+    /// Where the element with id `id` currently is.
+    pub fn position(&self, id: usize) -> usize {
+        let b = self.block_of[id];
+        let before: usize = self.blocks[..b].iter().map(Vec::len).sum();
+        before
+            + self.blocks[b]
+                .iter()
+                .position(|&other| other == id)
+                .unwrap()
+    }
+
+    // The block holding `index` and the index within that block. An index one
+    // past the end is at the end of the last block, which there must be.
+    fn locate(&self, mut index: usize) -> (usize, usize) {
+        for (b, block) in self.blocks.iter().enumerate() {
+            if index < block.len() {
+                return (b, index);
+            }
+            index -= block.len();
+        }
+        let last = self.blocks.len() - 1;
+        (last, self.blocks[last].len() + index)
+    }
+
+    /// The id of the element at `index`.
+    pub fn id_at(&self, index: usize) -> Option<usize> {
+        if index >= self.len() {
+            return None;
+        }
+        let (b, i) = self.locate(index);
+        Some(self.blocks[b][i])
+    }
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.id_at(index).map(|id| self.value(id))
+    }
+
+    /// Moves the element with id `id` by `steps` places, treating the
+    /// sequence as a circle. The element isn't counted while it moves, so
+    /// moving by `len() - 1` brings it back where it was.
+    pub fn move_by(&mut self, id: usize, steps: i64) {
+        let others = self.len() as i64 - 1;
+        if others < 1 {
+            return;
+        }
+        let from = self.position(id);
+        let to = (from as i64 + steps).rem_euclid(others) as usize;
+        if from == to {
+            return;
+        }
+        let (b, i) = self.locate(from);
+        self.blocks[b].remove(i);
+        let (b, i) = self.locate(to);
+        self.blocks[b].insert(i, id);
+        self.block_of[id] = b;
+        if self.blocks[b].len() > 2 * self.block_size {
+            let order = self.blocks.concat();
+            self.rebuild(order);
+        }
+    }
+
+    /// The values in their current order.
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.blocks.iter().flatten().map(|&id| &self.values[id])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The same moves on a plain Vec
+    fn move_by(order: &mut Vec<usize>, id: usize, steps: i64) {
+        let from = order.iter().position(|&other| other == id).unwrap();
+        let to = (from as i64 + steps).rem_euclid(order.len() as i64 - 1) as usize;
+        order.remove(from);
+        order.insert(to, id);
+    }
+
+    #[test]
+    fn test_mix() {
+        let numbers = [1, 2, -3, 3, -2, 0, 4];
+        let mut sequence: Sequence<i64> = numbers.into_iter().collect();
+        for id in 0..sequence.len() {
+            sequence.move_by(id, *sequence.value(id));
+        }
+        let mixed: Vec<i64> = sequence.iter().copied().collect();
+        // The puzzle shows the same circle, starting from the 1
+        assert_eq!(mixed, vec![-2, 1, 2, -3, 4, 0, 3]);
+        assert_eq!(sequence.position(5), 5);
+        assert_eq!(sequence.get(6), Some(&3));
+        assert_eq!(sequence.get(7), None);
+    }
+
+    #[test]
+    fn test_matches_vec() {
+        let mut sequence: Sequence<usize> = (0..50).collect();
+        let mut order: Vec<usize> = (0..50).collect();
+        let mut random: i64 = 12345;
+        for _ in 0..2000 {
+            random = (random * 1103515245 + 12345) % (1 << 31);
+            let id = (random % 50) as usize;
+            let steps = random % 201 - 100;
+            sequence.move_by(id, steps);
+            move_by(&mut order, id, steps);
+        }
+        assert_eq!(sequence.iter().copied().collect::<Vec<_>>(), order);
+        assert!(order
+            .iter()
+            .enumerate()
+            .all(|(i, &id)| sequence.position(id) == i));
+    }
+}
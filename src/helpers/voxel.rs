@@ -0,0 +1,305 @@
+// Solid shapes made of unit cubes, like day 18's lava droplet.
+//
+// The grid is dense and covers the bounding box of the cubes plus one layer of
+// empty space on every side. That layer wraps around the whole shape, so a
+// flood fill started in one of its corners reaches every bit of air that's
+// outside, and nothing that's trapped inside.
+use std::collections::{HashMap, VecDeque};
+
+pub type Voxel = (i32, i32, i32);
+
+/// The six neighbours that share a face with a voxel.
+pub const FACES: [Voxel; 6] = [
+    (1, 0, 0),
+    (-1, 0, 0),
+    (0, 1, 0),
+    (0, -1, 0),
+    (0, 0, 1),
+    (0, 0, -1),
+];
+
+fn add((x, y, z): Voxel, (dx, dy, dz): Voxel) -> Voxel {
+    (x + dx, y + dy, z + dz)
+}
+
+#[derive(Debug, Clone)]
+pub struct VoxelGrid {
+    min: Voxel,
+    max: Voxel,
+    cells: Vec<bool>,
+}
+
+impl FromIterator<Voxel> for VoxelGrid {
+    fn from_iter<I: IntoIterator<Item = Voxel>>(iter: I) -> Self {
+        let voxels: Vec<Voxel> = iter.into_iter().collect();
+        let bound = |axis: fn(&Voxel) -> i32| {
+            let values = voxels.iter().map(axis);
+            (
+                values.clone().min().unwrap_or(0) - 1,
+                values.max().unwrap_or(0) + 1,
+            )
+        };
+        let (x, y, z) = (bound(|v| v.0), bound(|v| v.1), bound(|v| v.2));
+        let size = ((x.1 - x.0 + 1) * (y.1 - y.0 + 1) * (z.1 - z.0 + 1)) as usize;
+        let mut grid = VoxelGrid {
+            min: (x.0, y.0, z.0),
+            max: (x.1, y.1, z.1),
+            cells: vec![false; size],
+        };
+        for voxel in voxels {
+            let i = grid.index(voxel).unwrap();
+            grid.cells[i] = true;
+        }
+        grid
+    }
+}
+
+impl VoxelGrid {
+    fn index(&self, (x, y, z): Voxel) -> Option<usize> {
+        let (min, max) = (self.min, self.max);
+        if x < min.0 || y < min.1 || z < min.2 || x > max.0 || y > max.1 || z > max.2 {
+            return None;
+        }
+        let (height, depth) = ((max.1 - min.1 + 1) as usize, (max.2 - min.2 + 1) as usize);
+        let (x, y, z) = (
+            (x - min.0) as usize,
+            (y - min.1) as usize,
+            (z - min.2) as usize,
+        );
+        Some((x * height + y) * depth + z)
+    }
+
+    fn voxel(&self, index: usize) -> Voxel {
+        let (height, depth) = (
+            (self.max.1 - self.min.1 + 1) as usize,
+            (self.max.2 - self.min.2 + 1) as usize,
+        );
+        (
+            self.min.0 + (index / (height * depth)) as i32,
+            self.min.1 + (index / depth % height) as i32,
+            self.min.2 + (index % depth) as i32,
+        )
+    }
+
+    pub fn contains(&self, voxel: Voxel) -> bool {
+        self.index(voxel).is_some_and(|i| self.cells[i])
+    }
+
+    pub fn voxels(&self) -> impl Iterator<Item = Voxel> + '_ {
+        (0..self.cells.len())
+            .filter(|&i| self.cells[i])
+            .map(|i| self.voxel(i))
+    }
+
+    // Every face of a voxel that isn't against another voxel, as the voxel
+    // and the direction the face points in
+    fn faces(&self) -> impl Iterator<Item = (Voxel, Voxel)> + '_ {
+        self.voxels().flat_map(move |voxel| {
+            FACES
+                .iter()
+                .filter(move |&&direction| !self.contains(add(voxel, direction)))
+                .map(move |&direction| (voxel, direction))
+        })
+    }
+
+    /// The number of faces not touching another voxel, including the ones
+    /// facing air trapped inside the shape.
+    pub fn exposed_faces(&self) -> usize {
+        self.faces().count()
+    }
+
+    /// The empty cells connected to the outside of the shape. Everything
+    /// beyond the grid is outside too.
+    pub fn exterior(&self) -> VoxelGrid {
+        let mut outside = VoxelGrid {
+            min: self.min,
+            max: self.max,
+            cells: vec![false; self.cells.len()],
+        };
+        let mut queue = VecDeque::from([self.min]);
+        outside.cells[0] = true;
+        while let Some(voxel) = queue.pop_front() {
+            for direction in FACES {
+                let next = add(voxel, direction);
+                if let Some(i) = self.index(next) {
+                    if !self.cells[i] && !outside.cells[i] {
+                        outside.cells[i] = true;
+                        queue.push_back(next);
+                    }
+                }
+            }
+        }
+        outside
+    }
+
+    /// The number of faces that can be reached from outside the shape.
+    pub fn exterior_faces(&self) -> usize {
+        let outside = self.exterior();
+        self.faces()
+            .filter(|&(voxel, direction)| self.is_outside(&outside, add(voxel, direction)))
+            .count()
+    }
+
+    fn is_outside(&self, outside: &VoxelGrid, voxel: Voxel) -> bool {
+        self.index(voxel).is_none() || outside.contains(voxel)
+    }
+
+    /// Groups of voxels that are connected through their faces.
+    pub fn components(&self) -> Vec<Vec<Voxel>> {
+        let mut seen = vec![false; self.cells.len()];
+        let mut components = vec![];
+        for start in self.voxels() {
+            let i = self.index(start).unwrap();
+            if seen[i] {
+                continue;
+            }
+            seen[i] = true;
+            let mut component = vec![];
+            let mut queue = VecDeque::from([start]);
+            while let Some(voxel) = queue.pop_front() {
+                component.push(voxel);
+                for direction in FACES {
+                    let next = add(voxel, direction);
+                    if let Some(i) = self.index(next) {
+                        if self.cells[i] && !seen[i] {
+                            seen[i] = true;
+                            queue.push_back(next);
+                        }
+                    }
+                }
+            }
+            components.push(component);
+        }
+        components
+    }
+
+    /// The surface of the shape as a mesh of squares. With `exterior_only`
+    /// the walls of air pockets inside the shape are left out.
+    pub fn surface(&self, exterior_only: bool) -> Mesh {
+        let outside = self.exterior();
+        let mut mesh = Mesh::default();
+        let mut vertices: HashMap<Voxel, usize> = HashMap::new();
+        for (voxel, direction) in self.faces() {
+            if exterior_only && !self.is_outside(&outside, add(voxel, direction)) {
+                continue;
+            }
+            let face = square(voxel, direction).map(|corner| {
+                *vertices.entry(corner).or_insert_with(|| {
+                    mesh.vertices.push(corner);
+                    mesh.vertices.len() - 1
+                })
+            });
+            mesh.faces.push(face);
+        }
+        mesh
+    }
+}
+
+// The corners of the face of the unit cube at `voxel` that points in
+// `direction`, counter-clockwise when looking at it from outside
+fn square(voxel: Voxel, direction: Voxel) -> [Voxel; 4] {
+    let position = [voxel.0, voxel.1, voxel.2];
+    let normal = [direction.0, direction.1, direction.2];
+    let axis = normal.iter().position(|&n| n != 0).unwrap();
+    let (u, w) = ((axis + 1) % 3, (axis + 2) % 3);
+    let mut corners = [(0, 0), (1, 0), (1, 1), (0, 1)];
+    if normal[axis] < 0 {
+        corners.reverse();
+    }
+    corners.map(|(du, dw)| {
+        let mut corner = position;
+        corner[axis] += (normal[axis] > 0) as i32;
+        corner[u] += du;
+        corner[w] += dw;
+        (corner[0], corner[1], corner[2])
+    })
+}
+
+/// Squares sharing their corners, ready to be written out for a 3D viewer.
+#[derive(Debug, Clone, Default)]
+pub struct Mesh {
+    pub vertices: Vec<Voxel>,
+    /// Indices into `vertices`, counter-clockwise seen from outside.
+    pub faces: Vec<[usize; 4]>,
+}
+
+impl Mesh {
+    /// Wavefront OBJ, where vertices are numbered from 1.
+    pub fn to_obj(&self) -> String {
+        let mut obj = String::new();
+        for (x, y, z) in &self.vertices {
+            obj += &format!("v {} {} {}\n", x, y, z);
+        }
+        for [a, b, c, d] in &self.faces {
+            obj += &format!("f {} {} {} {}\n", a + 1, b + 1, c + 1, d + 1);
+        }
+        obj
+    }
+
+    /// ASCII PLY.
+    pub fn to_ply(&self) -> String {
+        let mut ply = format!(
+            "ply\nformat ascii 1.0\nelement vertex {}\nproperty int x\nproperty int y\nproperty int z\nelement face {}\nproperty list uchar int vertex_indices\nend_header\n",
+            self.vertices.len(),
+            self.faces.len()
+        );
+        for (x, y, z) in &self.vertices {
+            ply += &format!("{} {} {}\n", x, y, z);
+        }
+        for [a, b, c, d] in &self.faces {
+            ply += &format!("4 {} {} {} {}\n", a, b, c, d);
+        }
+        ply
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A 3x3x3 cube with the middle missing, and a separate cube next to it
+    fn hollow_cube() -> VoxelGrid {
+        let mut voxels: Vec<Voxel> = vec![];
+        for x in 0..3 {
+            for y in 0..3 {
+                for z in 0..3 {
+                    if (x, y, z) != (1, 1, 1) {
+                        voxels.push((x, y, z));
+                    }
+                }
+            }
+        }
+        voxels.push((5, 0, 0));
+        voxels.into_iter().collect()
+    }
+
+    #[test]
+    fn test_faces() {
+        let grid = hollow_cube();
+        assert_eq!(grid.voxels().count(), 27);
+        assert_eq!(grid.exposed_faces(), 9 * 6 + 6 + 6);
+        assert_eq!(grid.exterior_faces(), 9 * 6 + 6);
+        assert!(!grid.exterior().contains((1, 1, 1)));
+        assert!(grid.exterior().contains((4, 0, 0)));
+        let mut sizes: Vec<usize> = grid.components().iter().map(Vec::len).collect();
+        sizes.sort();
+        assert_eq!(sizes, vec![1, 26]);
+    }
+
+    #[test]
+    fn test_mesh() {
+        let grid: VoxelGrid = [(0, 0, 0)].into_iter().collect();
+        let mesh = grid.surface(true);
+        assert_eq!(mesh.vertices.len(), 8);
+        assert_eq!(mesh.faces.len(), 6);
+        // The face pointing up is counter-clockwise seen from above
+        let up = mesh.faces[2].map(|i| mesh.vertices[i]);
+        assert_eq!(up, [(0, 1, 0), (0, 1, 1), (1, 1, 1), (1, 1, 0)]);
+        let obj = mesh.to_obj();
+        assert_eq!(obj.lines().filter(|line| line.starts_with("v ")).count(), 8);
+        assert!(obj.contains("f 1 2 3 4\n"));
+        assert!(mesh.to_ply().contains("element vertex 8\n"));
+        assert_eq!(hollow_cube().surface(false).faces.len(), 66);
+        assert_eq!(hollow_cube().surface(true).faces.len(), 60);
+    }
+}